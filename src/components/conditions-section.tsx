@@ -157,6 +157,35 @@ export default function ConditionsSection({
                     </DropdownMenuRadioGroup>
                   </DropdownMenuSubContent>
                 </DropdownMenuSub>
+                {(["data1Kind", "data2Kind"] as const).map((kind, i) => (
+                  <DropdownMenuSub key={kind + condition.id}>
+                    <DropdownMenuSubTrigger>
+                      {i === 0 ? "First" : "Second"} Operand:{" "}
+                      {condition[kind] ?? "unset"}
+                    </DropdownMenuSubTrigger>
+                    <DropdownMenuSubContent>
+                      <DropdownMenuRadioGroup
+                        value={condition[kind]}
+                        onValueChange={(v) => {
+                          const idx = selectedNode.data.conditions.findIndex(
+                            (el: any) => el.id === condition.id,
+                          );
+                          selectedNode.data.conditions[idx][kind] = v;
+                          updateNode(selectedNode.id, {
+                            ...selectedNode.data,
+                          });
+                        }}
+                      >
+                        <DropdownMenuRadioItem value="reference">
+                          Data reference
+                        </DropdownMenuRadioItem>
+                        <DropdownMenuRadioItem value="literal">
+                          Literal value
+                        </DropdownMenuRadioItem>
+                      </DropdownMenuRadioGroup>
+                    </DropdownMenuSubContent>
+                  </DropdownMenuSub>
+                ))}
                 <DropdownMenuLabel>Combine With</DropdownMenuLabel>
                 <DropdownMenuSeparator />
                 <DropdownMenuRadioGroup
@@ -215,6 +244,8 @@ export default function ConditionsSection({
                 id: uuidv4(),
                 combinedWith: "and",
                 type: "string",
+                data1Kind: "reference",
+                data2Kind: "literal",
               });
               updateNode(selectedNode.id, {
                 ...selectedNode.data,
@@ -234,6 +265,8 @@ export default function ConditionsSection({
                 id: uuidv4(),
                 combinedWith: "or",
                 type: "string",
+                data1Kind: "reference",
+                data2Kind: "literal",
               });
               updateNode(selectedNode.id, {
                 ...selectedNode.data,
@@ -19,10 +19,27 @@ import FunctionNode from "./nodes/function-node";
 import StartNode from "./nodes/start-node";
 import EndNode from "./nodes/end-node";
 import ParallelNode from "./nodes/parallel-node";
+import ParallelSectionsNode from "./nodes/parallel-sections-node";
 import IfNode from "./nodes/if-node";
 import WhileNode from "./nodes/while-node";
+import ForNode from "./nodes/for-node";
+import SubFCNode from "./nodes/sub-fc-node";
+import SwitchNode from "./nodes/switch-node";
 import { uuidv4 } from "@/lib/helpers";
 
+/** Node types that hold child nodes. */
+const compoundTypes: (string | undefined)[] = [
+  "parallel",
+  "parallelSections",
+  "while",
+  "for",
+  "if",
+  "switch",
+  "subFC",
+];
+/** Compounds whose merged outputs are `ifDataOuts`. */
+const branchingTypes: (string | undefined)[] = ["if", "switch"];
+
 interface FlowViewProps {
   nodes: Node[];
   setNodes: React.Dispatch<React.SetStateAction<Node[]>>;
@@ -54,8 +71,12 @@ export default function FlowView({
       start: StartNode,
       function: FunctionNode,
       parallel: ParallelNode,
+      parallelSections: ParallelSectionsNode,
       if: IfNode,
+      switch: SwitchNode,
       while: WhileNode,
+      for: ForNode,
+      subFC: SubFCNode,
       end: EndNode,
     }),
     [],
@@ -65,30 +86,28 @@ export default function FlowView({
     (params: Connection) => {
       const source = nodes.find((n) => n.id === params.source);
       const target = nodes.find((n) => n.id === params.target);
-      if (
-        source?.id === target?.id &&
-        target?.type !== "parallel" &&
-        target?.type !== "while" &&
-        target?.type !== "if"
-      )
+      if (source?.id === target?.id && !compoundTypes.includes(target?.type))
         return;
 
       const inputIndex = Number(params.sourceHandle?.substring(1));
       if (source && target && !isNaN(inputIndex)) {
         let input: DataIn | DataOut;
 
-        const isCompound =
-          source.type === "parallel" ||
-          source.type === "while" ||
-          source.type === "if";
+        const isCompound = compoundTypes.includes(source.type);
 
-        const isTargetCompound =
-          target.type === "parallel" ||
-          target.type === "while" ||
-          target.type === "if";
+        const isTargetCompound = compoundTypes.includes(target.type);
 
-        if (source.type === "if" && params.sourceHandle?.startsWith("o")) {
+        if (
+          branchingTypes.includes(source.type) &&
+          params.sourceHandle?.startsWith("o")
+        ) {
           input = source.data.ifDataOuts[inputIndex];
+        } else if (
+          source.type === "for" &&
+          params.sourceHandle?.startsWith("l")
+        ) {
+          // children read data loops like data produced by the for node
+          input = { id: source.data.dataLoops[inputIndex].id, source: source.id };
         } else {
           input =
             (isCompound && !params.sourceHandle?.startsWith("o") && !isTargetCompound)
@@ -100,12 +119,7 @@ export default function FlowView({
             (currentInputs: DataIn) => currentInputs.id === input.id,
           )
         ) {
-          if (
-            (target.type === "parallel" ||
-              target.type === "while" ||
-              target.type === "if") &&
-            params.targetHandle === "oidefault"
-          ) {
+          if (isTargetCompound && params.targetHandle === "oidefault") {
             updateNode(target.id, {
               ...target.data,
               dataOuts: [
@@ -130,7 +144,7 @@ export default function FlowView({
           }
           updateNodeInternals(target.id);
         } else if (
-          target.type === "if" &&
+          branchingTypes.includes(target.type) &&
           !target.data.dataOuts?.find((dout: DataOut) => dout.id === input.id)
         ) {
           updateNode(target.id, {
@@ -167,7 +181,7 @@ export default function FlowView({
           ...n,
           className:
             intersections.includes(n.id) &&
-            (n.type === "parallel" || n.type === "while" || n.type === "if") &&
+            compoundTypes.includes(n.type) &&
             node.parentNode !== n.id
               ? "shadow-[0_0_50px_15px_rgba(0,0,0,0.3)] rounded-lg"
               : "",
@@ -181,9 +195,8 @@ export default function FlowView({
     (_: React.MouseEvent, node: Node) => {
       if (node.type === "start" || node.type === "end") return;
       const intersections = getIntersectingNodes(node, false);
-      const intersectedBlock = intersections.findLast(
-        (n: any) =>
-          n.type === "parallel" || n.type === "while" || n.type === "if",
+      const intersectedBlock = intersections.findLast((n: any) =>
+        compoundTypes.includes(n.type),
       );
       if (intersectedBlock) {
         setNodes((ns) =>
@@ -231,10 +244,9 @@ export default function FlowView({
         id: type == "end" ? "end" : uuidv4(),
         type: type,
         position,
-        style:
-          type == "parallel" || type == "while" || type == "if"
-            ? { height: "250px", width: "250px" }
-            : undefined,
+        style: compoundTypes.includes(type)
+          ? { height: "250px", width: "250px" }
+          : undefined,
         data: getDefaultData(type as ApolloNodeType),
       } as Node;
 
@@ -1,4 +1,4 @@
-import { AlignVerticalJustifyCenter, Check, ChevronDownIcon, Circle, CircleDot, Copy, FunctionSquare, Github, Import, Info, MinusCircle, MoreHorizontal, Plus, PlusCircle, RotateCcw, Save, Settings, Split, Terminal, Trash2, TableProperties, X } from "lucide-react";
+import { AlignVerticalJustifyCenter, Check, ChevronDownIcon, Circle, CircleDot, Columns, Component, Copy, FunctionSquare, GitFork, Github, Import, Info, MinusCircle, MoreHorizontal, Plus, PlusCircle, Repeat, RotateCcw, Save, Settings, Split, Terminal, Trash2, TableProperties, X } from "lucide-react";
 import { Orbit } from "lucide-react";
 import { LucideIcon } from "lucide-react";
 import { ApolloNodeType } from "@/types";
@@ -9,8 +9,12 @@ export const NodeIcons:Record<ApolloNodeType, Icon> = {
   start: Circle,
   function: FunctionSquare,
   parallel: Copy,
+  parallelSections: Columns,
   if: Split,
+  switch: GitFork,
   while: RotateCcw,
+  for: Repeat,
+  subFC: Component,
   end: CircleDot,
 }
 
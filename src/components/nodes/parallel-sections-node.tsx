@@ -0,0 +1,106 @@
+import { Handle, Position, NodeProps, NodeResizer, useNodeId } from "reactflow";
+import { NodeIcons } from "../icons";
+import { Separator } from "../ui/separator";
+import { ParallelSectionsNode } from "@/types";
+import { useDataVariables } from "@/lib/helpers";
+
+export default function ParallelSectionsNode({
+  data,
+  selected,
+}: NodeProps<ParallelSectionsNode>) {
+  const { getDataInName, getDataOutName } = useDataVariables();
+  const nodeId = useNodeId() ?? "";
+  return (
+    <div className="flex flex-col justify-between items-center h-full border-2 rounded-lg text-center bg-indigo-500 -z-10 bg-opacity-10">
+      <NodeResizer isVisible={selected} minWidth={100} minHeight={100} />
+      <div className="inline-flex flex-col items-center shadow-md rounded-md bg-white border-2 -top-8 relative -mb-8">
+        <Handle
+          type="target"
+          position={Position.Top}
+          id={"idefault"}
+          className="bg-slate-200 w-3 h-2 rounded-b-full top-0"
+        />
+
+        <div className="p-2 flex flex-col items-center">
+          <div className="flex items-center text-xs text-slate-500">
+            <NodeIcons.parallelSections className="w-4 h-3" />
+            Parallel sections
+          </div>
+          <div className="text-sm text-slate-800 font-semibold">
+            {data.name.length ? data.name : "parallel name"}
+          </div>
+        </div>
+        {data.dataIns.length > 0 && (
+          <>
+            <Separator />
+            <div className="flex space-x-2 mx-2">
+              {data.dataIns.map((dataIn, idx) => (
+                <p
+                  key={idx}
+                  className="mt-2 text-xs text-slate-700 bg-slate-200 rounded px-2 h-[16px]"
+                >
+                  {getDataInName(nodeId, dataIn)}
+                </p>
+              ))}
+            </div>
+          </>
+        )}
+        <Separator className="mt-2" />
+        <div className="flex space-x-2 mx-2 mb-0.5">
+          {data.sections?.map((section, idx) => (
+            <div key={section.id} className="mt-2">
+              <p className="text-xs text-slate-700 bg-slate-200 rounded px-2 h-[16px]">
+                {"Section " + (idx + 1)}
+              </p>
+              <Handle
+                type="source"
+                position={Position.Bottom}
+                id={section.id}
+                className="bg-slate-200 w-3 h-2 rounded-t-full relative"
+              />
+            </div>
+          ))}
+        </div>
+      </div>
+
+      <div className="flex flex-col items-center shadow-md rounded-md bg-white border-2 top-4 relative">
+        <Handle
+          type="target"
+          position={Position.Top}
+          id={"oidefault"}
+          className="bg-slate-200 w-3 h-2 rounded-b-full top-0"
+        />
+
+        <div className="p-2 flex flex-col items-center">
+          <div className="flex items-center text-xs text-slate-500">
+            Outputs
+          </div>
+        </div>
+        {data.dataOuts.length > 0 ? (
+          <div className="flex space-x-2 mx-2 mb-0.5">
+            {data.dataOuts.map((dataOut, idx) => (
+              <div key={idx}>
+                <p className="text-xs text-slate-700 bg-slate-200 rounded px-2 h-[16px]">
+                  {getDataOutName(dataOut)}
+                </p>
+                <Handle
+                  type="source"
+                  position={Position.Bottom}
+                  id={"o" + idx}
+                  className="bg-slate-200 w-3 h-2 rounded-t-full relative"
+                />
+              </div>
+            ))}
+          </div>
+        ) : (
+          <Handle
+            type="source"
+            position={Position.Bottom}
+            id={"oodefault"}
+            className="bg-slate-200 w-3 h-2 rounded-t-full -bottom-0.5 "
+          />
+        )}
+      </div>
+    </div>
+  );
+}
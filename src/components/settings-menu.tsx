@@ -1,3 +1,4 @@
+import { CSSProperties, useRef, useState } from "react";
 import { getDateTimeString } from "@/lib/helpers";
 import { Icons } from "./icons";
 import { Button } from "./ui/button";
@@ -11,9 +12,47 @@ import {
   DropdownMenuTrigger,
 } from "./ui/dropdown-menu";
 import { Workflow } from "@/types";
-import { downloadGraph, exportApolloYaml } from "@/lib/exporter";
+import {
+  NodeState,
+  Problem,
+  downloadDot,
+  downloadGraph,
+  exportApolloYaml,
+  exportWithSourceMap,
+  explainExport,
+  logHeatmap,
+  replayTrace,
+} from "@/lib/exporter";
 import { useReactFlow } from "reactflow";
 
+/** Classes marking a node the last export failed on. */
+const errorRing = ["ring-4", "ring-red-500", "rounded-lg"];
+
+/** Adds or removes the error ring, keeping all other classes of the node. */
+function withErrorRing(className: string | undefined, broken: boolean) {
+  const classes = (className ?? "")
+    .split(" ")
+    .filter((c) => c.length && !errorRing.includes(c));
+  return [...classes, ...(broken ? errorRing : [])].join(" ");
+}
+
+/** Outline colors of the nodes of a replayed trace. */
+const statusColors: Record<NodeState["status"], string> = {
+  running: "rgb(245 158 11)",
+  done: "rgb(34 197 94)",
+  failed: "rgb(239 68 68)",
+};
+
+/** Outlines a node in `color`, or removes the outline, keeping all other styles. */
+function withOverlay(style: CSSProperties | undefined, color: string | null) {
+  const rest = { ...style };
+  delete rest.boxShadow;
+  return color ? { ...rest, boxShadow: `0 0 0 4px ${color}` } : rest;
+}
+
+/** A trace loaded for replay, `position` counts the steps shown so far. */
+type Replay = { trace: any; position: number; steps: number };
+
 interface SettingsMenuProps {
   selectedWorkflow: Workflow;
   leavePage: () => void;
@@ -23,57 +62,222 @@ export default function SettingsMenu({
   selectedWorkflow,
   leavePage,
 }: SettingsMenuProps) {
-  const { toObject } = useReactFlow();
+  const { toObject, setNodes } = useReactFlow();
+  const traceInputRef = useRef<HTMLInputElement>(null);
+  const logInputRef = useRef<HTMLInputElement>(null);
+  const [replay, setReplay] = useState<Replay | null>(null);
+  const [heatmap, setHeatmap] = useState(false);
+
+  const markBrokenNodes = (errors: Problem[]) => {
+    const brokenNodes = errors.map((e) => e.nodeId);
+    setNodes((ns) =>
+      ns.map((n) => ({
+        ...n,
+        className: withErrorRing(n.className, brokenNodes.includes(n.id)),
+      })),
+    );
+  };
+
+  const exportWorkflow = (withSourceMap: boolean) => {
+    const workflow = { name: selectedWorkflow.name, data: toObject() };
+    markBrokenNodes(
+      withSourceMap
+        ? exportWithSourceMap(workflow)
+        : exportApolloYaml(workflow),
+    );
+  };
+
+  const exportDot = (exported: boolean) =>
+    markBrokenNodes(
+      downloadDot({ name: selectedWorkflow.name, data: toObject() }, exported),
+    );
+
+  const showStep = (trace: any, step: number) => {
+    const { steps, position, nodes, error } = replayTrace(trace, step);
+    if (error) {
+      alert("Could not read trace:\n" + error);
+      return;
+    }
+    setNodes((ns) =>
+      ns.map((n) => ({
+        ...n,
+        style: withOverlay(
+          n.style,
+          nodes[n.id] ? statusColors[nodes[n.id].status] : null,
+        ),
+      })),
+    );
+    setReplay({ trace, position, steps });
+    setHeatmap(false);
+  };
+
+  const showHeatmap = (log: string) => {
+    const heat = logHeatmap(
+      { name: selectedWorkflow.name, data: toObject() },
+      log,
+    );
+    if (!heat) return;
+    console.table(heat.nodes);
+    if (heat.unmatched.length)
+      console.warn("logged functions not in the graph:", heat.unmatched);
+    setNodes((ns) =>
+      ns.map((n) => ({
+        ...n,
+        style: withOverlay(
+          n.style,
+          heat.nodes[n.id]
+            ? `rgb(239 68 68 / ${0.2 + 0.8 * heat.nodes[n.id].heat})`
+            : null,
+        ),
+      })),
+    );
+    setHeatmap(true);
+    setReplay(null);
+  };
+
+  const clearOverlay = () => {
+    setNodes((ns) =>
+      ns.map((n) => ({ ...n, style: withOverlay(n.style, null) })),
+    );
+    setReplay(null);
+    setHeatmap(false);
+  };
 
   return (
-    <DropdownMenu>
-      <DropdownMenuTrigger asChild>
-        <Button size={"icon"} className="mb-4">
-          <Icons.settings className="h-8 w-8" />
-        </Button>
-      </DropdownMenuTrigger>
-      <DropdownMenuContent className="w-56" align="start" forceMount>
-        <DropdownMenuLabel className="font-normal">
-          <div className="flex flex-col space-y-1">
-            <p className="text-sm font-medium leading-none">
-              {selectedWorkflow.name}
-            </p>
-            <p className="text-xs leading-none text-muted-foreground">
-              Last saved:{" "}
-              {selectedWorkflow.lastSaved
-                ? getDateTimeString(selectedWorkflow.lastSaved)
-                : "never"}
-            </p>
-          </div>
-        </DropdownMenuLabel>
-        <DropdownMenuSeparator />
-        <DropdownMenuGroup>
-          <DropdownMenuItem
-            onClick={() =>
-              exportApolloYaml({
-                name: selectedWorkflow.name,
-                data: toObject(),
-              })
-            }
-          >
-            Export Apollo Yaml
-          </DropdownMenuItem>
-          <DropdownMenuItem
-            onClick={() =>
-              downloadGraph({
-                name: selectedWorkflow.name,
-                data: toObject(),
-              })
-            }
-          >
-            Download Graph
+    <>
+      <input
+        type="file"
+        accept=".json"
+        ref={traceInputRef}
+        className="hidden"
+        onChange={(e) => {
+          if (e.target.files?.length) {
+            const reader = new FileReader();
+            reader.onload = (e) =>
+              showStep(JSON.parse(e.target?.result as string), 0);
+            reader.readAsText(e.target.files[0]);
+            e.target.value = "";
+          }
+        }}
+      />
+      <input
+        type="file"
+        accept=".json,.csv"
+        ref={logInputRef}
+        className="hidden"
+        onChange={(e) => {
+          if (e.target.files?.length) {
+            const reader = new FileReader();
+            reader.onload = (e) => showHeatmap(e.target?.result as string);
+            reader.readAsText(e.target.files[0]);
+            e.target.value = "";
+          }
+        }}
+      />
+      <DropdownMenu>
+        <DropdownMenuTrigger asChild>
+          <Button size={"icon"} className="mb-4">
+            <Icons.settings className="h-8 w-8" />
+          </Button>
+        </DropdownMenuTrigger>
+        <DropdownMenuContent className="w-56" align="start" forceMount>
+          <DropdownMenuLabel className="font-normal">
+            <div className="flex flex-col space-y-1">
+              <p className="text-sm font-medium leading-none">
+                {selectedWorkflow.name}
+              </p>
+              <p className="text-xs leading-none text-muted-foreground">
+                Last saved:{" "}
+                {selectedWorkflow.lastSaved
+                  ? getDateTimeString(selectedWorkflow.lastSaved)
+                  : "never"}
+              </p>
+            </div>
+          </DropdownMenuLabel>
+          <DropdownMenuSeparator />
+          <DropdownMenuGroup>
+            <DropdownMenuItem onClick={() => exportWorkflow(false)}>
+              Export Apollo Yaml
+            </DropdownMenuItem>
+            <DropdownMenuItem onClick={() => exportWorkflow(true)}>
+              Export Yaml with Source Map
+            </DropdownMenuItem>
+            <DropdownMenuItem
+              onClick={() =>
+                downloadGraph({
+                  name: selectedWorkflow.name,
+                  data: toObject(),
+                })
+              }
+            >
+              Download Graph
+            </DropdownMenuItem>
+            <DropdownMenuItem
+              onClick={() =>
+                explainExport({
+                  name: selectedWorkflow.name,
+                  data: toObject(),
+                })
+              }
+            >
+              Log Data Sources
+            </DropdownMenuItem>
+            <DropdownMenuItem onClick={() => exportDot(false)}>
+              Download Graph as DOT
+            </DropdownMenuItem>
+            <DropdownMenuItem onClick={() => exportDot(true)}>
+              Download Yaml as DOT
+            </DropdownMenuItem>
+          </DropdownMenuGroup>
+          <DropdownMenuSeparator />
+          <DropdownMenuGroup>
+            <DropdownMenuItem onClick={() => traceInputRef.current?.click()}>
+              Replay Trace
+            </DropdownMenuItem>
+            {replay && (
+              <>
+                <DropdownMenuLabel className="text-xs font-normal text-muted-foreground">
+                  Step {replay.position} of {replay.steps}
+                </DropdownMenuLabel>
+                <DropdownMenuItem
+                  disabled={replay.position == 0}
+                  onSelect={(e) => {
+                    // keep the menu open to step on
+                    e.preventDefault();
+                    showStep(replay.trace, replay.position - 1);
+                  }}
+                >
+                  Step Back
+                </DropdownMenuItem>
+                <DropdownMenuItem
+                  disabled={replay.position == replay.steps}
+                  onSelect={(e) => {
+                    e.preventDefault();
+                    showStep(replay.trace, replay.position + 1);
+                  }}
+                >
+                  Step Forward
+                </DropdownMenuItem>
+                <DropdownMenuItem onClick={clearOverlay}>
+                  Stop Replay
+                </DropdownMenuItem>
+              </>
+            )}
+            <DropdownMenuItem onClick={() => logInputRef.current?.click()}>
+              Show Run Log Heatmap
+            </DropdownMenuItem>
+            {heatmap && (
+              <DropdownMenuItem onClick={clearOverlay}>
+                Clear Heatmap
+              </DropdownMenuItem>
+            )}
+          </DropdownMenuGroup>
+          <DropdownMenuSeparator />
+          <DropdownMenuItem onClick={leavePage}>
+            Back to Dashboard
           </DropdownMenuItem>
-        </DropdownMenuGroup>
-        <DropdownMenuSeparator />
-        <DropdownMenuItem onClick={leavePage}>
-          Back to Dashboard
-        </DropdownMenuItem>
-      </DropdownMenuContent>
-    </DropdownMenu>
+        </DropdownMenuContent>
+      </DropdownMenu>
+    </>
   );
 }
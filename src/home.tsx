@@ -17,6 +17,7 @@ import {
 } from "@/components/ui/tooltip";
 import WorkflowActions from "@/components/workflow-actions";
 import { WORKFLOW_KEY_PREFIX, getDateTimeString, uuidv4 } from "@/lib/helpers";
+import { importApolloYaml } from "@/lib/exporter";
 
 import logo from "./apollo_logo.png";
 import { Workflow } from "./types";
@@ -182,9 +183,13 @@ export default function Home({ selectWorkflow }: HomeProps) {
                       const file = e.target.files[0];
                       const reader = new FileReader();
                       reader.onload = (e) => {
-                        const workflow = JSON.parse(
-                          e.target?.result as string,
-                        ) as Workflow;
+                        const content = e.target?.result as string;
+                        const workflow = (
+                          /\.ya?ml$/.test(file.name)
+                            ? importApolloYaml(content)
+                            : JSON.parse(content)
+                        ) as Workflow | null;
+                        if (!workflow) return;
                         workflow.id = WORKFLOW_KEY_PREFIX + uuidv4();
                         workflow.lastSaved = null;
                         localStorage.setItem(
@@ -1,20 +1,30 @@
-import { convert_to_wf_yaml } from "../../wf-exporter/pkg";
+import {
+  convert_from_wf_yaml,
+  convert_to_dot,
+  convert_to_wf_yaml,
+  log_overlay,
+  replay_trace,
+} from "../../wf-exporter/pkg";
 
-export function exportApolloYaml(workflow: { name: string; data: any }) {
-  console.log(workflow);
-  let result = "";
-  try {
-    result = convert_to_wf_yaml(workflow);
-  } catch (e) {
-    alert("Could not export workflow. Check console for more detailed error.");
-  }
+export type Diagnostic = {
+  kind: string;
+  nodeId: string | null;
+  dataId: string | null;
+  message: string;
+};
 
-  if (result.length == 0) return;
+/** Finding of a check run before the export, `rule` is a stable id like `duplicateName`. */
+export type Lint = {
+  rule: string;
+  severity: "error" | "warning";
+  nodeId: string;
+  dataId: string | null;
+  message: string;
+};
 
-  const file = new File(["\ufeff" + result], `${workflow.name}.yaml`, {
-    type: "text/plain:charset=UTF-8",
-  });
+export type Problem = Diagnostic | Lint;
 
+function download(file: File) {
   const url = window.URL.createObjectURL(file);
 
   const a = document.createElement("a");
@@ -24,6 +34,233 @@ export function exportApolloYaml(workflow: { name: string; data: any }) {
   window.URL.revokeObjectURL(url);
 }
 
+function reportDiagnostics(errors: Problem[], warnings: Problem[]) {
+  warnings.forEach((w) => console.warn(w));
+  if (errors.length)
+    alert(
+      "Could not convert workflow:\n" + errors.map((e) => e.message).join("\n"),
+    );
+}
+
+export function exportApolloYaml(workflow: {
+  name: string;
+  data: any;
+}): Problem[] {
+  const { yaml, errors, warnings, lints } = convert_to_wf_yaml(workflow);
+  const problems: Problem[] = [
+    ...errors,
+    ...lints.filter((l: Lint) => l.severity === "error"),
+  ];
+  reportDiagnostics(problems, [
+    ...warnings,
+    ...lints.filter((l: Lint) => l.severity === "warning"),
+  ]);
+
+  if (problems.length) return problems;
+
+  const file = new File(["\ufeff" + yaml], `${workflow.name}.yaml`, {
+    type: "text/plain:charset=UTF-8",
+  });
+
+  download(file);
+  return [];
+}
+
+/** Where a function or data of exported yaml came from in the graph, lines count from 1. */
+export type SourceMapEntry = {
+  path: string;
+  line: number;
+  endLine: number;
+  function: string;
+  data: string | null;
+  nodeId: string;
+  dataId: string | null;
+};
+
+/**
+ * Downloads the exported yaml along with `<name>.map.json`, the source map linking the yaml
+ * to the nodes, e.g. to find the node an Apollo error is about.
+ */
+export function exportWithSourceMap(workflow: {
+  name: string;
+  data: any;
+}): Problem[] {
+  const { yaml, sourceMap, errors, warnings, lints } = convert_to_wf_yaml(
+    workflow,
+    true,
+  );
+  const problems: Problem[] = [
+    ...errors,
+    ...lints.filter((l: Lint) => l.severity === "error"),
+  ];
+  reportDiagnostics(problems, [
+    ...warnings,
+    ...lints.filter((l: Lint) => l.severity === "warning"),
+  ]);
+  if (problems.length) return problems;
+
+  download(
+    new File(["\ufeff" + yaml], `${workflow.name}.yaml`, {
+      type: "text/plain:charset=UTF-8",
+    }),
+  );
+  const entries: SourceMapEntry[] = sourceMap.entries;
+  download(
+    new File([JSON.stringify(entries)], `${workflow.name}.map.json`, {
+      type: "application/json:charset=UTF-8",
+    }),
+  );
+  return [];
+}
+
+/** How the exporter found the `source` of a data in- or output, see `wf-export --explain`. */
+export type Explanation = {
+  nodeId: string;
+  node: string;
+  dataId: string;
+  direction: "in" | "out";
+  name: string;
+  source: string | null;
+  rule:
+    | "unconnected"
+    | "constant"
+    | "parallelForInput"
+    | "dataLoop"
+    | "branchOutput"
+    | "output"
+    | "enclosingInput"
+    | "mergedBranches";
+  visited: {
+    nodeId: string;
+    name: string;
+    role: "parent" | "source" | "sourceParent" | "enclosing";
+  }[];
+  message: string;
+};
+
+/** Exports the graph without downloading it and logs how every data source was found. */
+export function explainExport(workflow: {
+  name: string;
+  data: any;
+}): Explanation[] {
+  const { explanations } = convert_to_wf_yaml(workflow, false, true);
+  console.groupCollapsed(`${workflow.name}: data sources`);
+  explanations.forEach((e: Explanation) => console.info(e.message));
+  console.groupEnd();
+  return explanations;
+}
+
+export function importApolloYaml(yaml: string) {
+  const { workflow, errors, warnings } = convert_from_wf_yaml(yaml);
+  reportDiagnostics(errors, warnings);
+  return workflow;
+}
+
+/** Logged calls of a node, durations are in milliseconds. */
+export type NodeStats = {
+  function: string;
+  calls: number;
+  failures: number;
+  iterations: number;
+  totalDuration: number;
+  minDuration: number;
+  maxDuration: number;
+  meanDuration: number;
+  /** Total duration relative to the node taking longest, from 0 to 1. */
+  heat: number;
+};
+
+/**
+ * Joins the function logs of an Apollo run, JSON or CSV, to the nodes of the graph.
+ * `nodes` is keyed by node id, `unmatched` lists logged functions not in the graph.
+ */
+export function logHeatmap(
+  workflow: { name: string; data: any },
+  log: string,
+): { nodes: Record<string, NodeStats>; unmatched: string[] } | null {
+  const { overlay, logError, errors, warnings, lints } = log_overlay(
+    workflow,
+    log,
+  );
+  reportDiagnostics(
+    [...errors, ...lints.filter((l: Lint) => l.severity === "error")],
+    [...warnings, ...lints.filter((l: Lint) => l.severity === "warning")],
+  );
+  if (logError) alert("Could not read log:\n" + logError);
+  return overlay;
+}
+
+/** A loop around a traced call, `index` counts iterations or parallel for elements from 0. */
+export type LoopFrame = {
+  function: string;
+  nodeId: string | null;
+  kind: "while" | "for" | "parallelFor";
+  index: number;
+};
+
+export type TraceEvent = {
+  seq: number;
+  function: string;
+  nodeId: string | null;
+  type: string;
+  startedAt: number;
+  endedAt: number;
+  inputs: Record<string, any>;
+  outputs: Record<string, any> | null;
+  error: string | null;
+  loops: LoopFrame[];
+};
+
+export type NodeState = {
+  status: "running" | "done" | "failed";
+  calls: number;
+  inputs: Record<string, any>;
+  outputs: Record<string, any> | null;
+  error: string | null;
+  loops: LoopFrame[];
+};
+
+/** State of every node after `step` steps of a trace written by `wf-run --trace`. */
+export function replayTrace(
+  trace: any,
+  step: number,
+): {
+  steps: number;
+  position: number;
+  current: TraceEvent | null;
+  nodes: Record<string, NodeState>;
+  error: string | null;
+} {
+  return replay_trace(trace, step);
+}
+
+/**
+ * Downloads the graph as Graphviz DOT, or with `exported` the AFCL it is exported to.
+ * Render it with `dot -Tsvg`.
+ */
+export function downloadDot(
+  workflow: { name: string; data: any },
+  exported = false,
+): Problem[] {
+  const { dot, errors, warnings, lints } = convert_to_dot(workflow, exported);
+  const problems: Problem[] = [
+    ...errors,
+    ...lints.filter((l: Lint) => l.severity === "error"),
+  ];
+  reportDiagnostics(problems, [
+    ...warnings,
+    ...lints.filter((l: Lint) => l.severity === "warning"),
+  ]);
+  if (problems.length) return problems;
+
+  const file = new File([dot], `${workflow.name}.dot`, {
+    type: "text/vnd.graphviz:charset=UTF-8",
+  });
+
+  download(file);
+  return [];
+}
+
 export function downloadGraph(workflow: { name: string; data: any }) {
   const result = JSON.stringify(workflow);
 
@@ -33,11 +270,5 @@ export function downloadGraph(workflow: { name: string; data: any }) {
     type: "text/plain:charset=UTF-8",
   });
 
-  const url = window.URL.createObjectURL(file);
-
-  const a = document.createElement("a");
-  a.href = url;
-  a.download = file.name;
-  a.click();
-  window.URL.revokeObjectURL(url);
+  download(file);
 }
@@ -2,8 +2,12 @@ export type ApolloNodeType =
   | "start"
   | "function"
   | "parallel"
+  | "parallelSections"
   | "if"
+  | "switch"
   | "while"
+  | "for"
+  | "subFC"
   | "end";
 
 export const dataTypes = [
@@ -55,6 +59,9 @@ export type Condition = {
   id: string;
   data1: string;
   data2: string;
+  /** Unset operands containing a `/` are references if they name data, literals otherwise. */
+  data1Kind?: "reference" | "literal";
+  data2Kind?: "reference" | "literal";
   type: string;
   operator: string;
   negation: boolean;
@@ -114,6 +121,8 @@ export type Iterator = {
 
 export type LoopCounter = {
   enabled: boolean;
+  name?: string;
+  from?: string;
   to: string;
   type: string;
   step: string;
@@ -129,6 +138,19 @@ export type ParallelNode = {
   loopCounter?: LoopCounter;
 };
 
+export type Section = {
+  id: string;
+};
+
+export type ParallelSectionsNode = {
+  name: string;
+  dataIns: DataIn[];
+  dataOuts: DataOut[];
+  properties?: Property[];
+  constraints?: Constraint[];
+  sections: Section[];
+};
+
 export type WhileNode = {
   name: string;
   dataIns: DataIn[];
@@ -138,6 +160,35 @@ export type WhileNode = {
   conditions: Condition[];
 };
 
+export type DataLoop = {
+  id: string;
+  name: string;
+  type?: DataType;
+  initSource?: DataIn;
+  loopSource?: DataIn;
+  value?: string;
+};
+
+export type ForNode = {
+  name: string;
+  dataIns: DataIn[];
+  dataOuts: DataOut[];
+  properties?: Property[];
+  constraints?: Constraint[];
+  loopCounter: LoopCounter;
+  dataLoops?: DataLoop[];
+};
+
+/** Definition of a reusable sub-FC, `type` is the name function nodes call it by. */
+export type SubFCNode = {
+  name: string;
+  type: string;
+  dataIns: DataIn[];
+  dataOuts: DataOut[];
+  properties?: Property[];
+  constraints?: Constraint[];
+};
+
 export type IfDataIn = DataIn & {
   sendToTrue: boolean;
   sendToFalse: boolean;
@@ -162,6 +213,22 @@ export type IfNode = {
   conditions: Condition[];
 };
 
+export type SwitchCase = {
+  id: string;
+  value: string;
+};
+
+export type SwitchNode = {
+  name: string;
+  dataIns: IfDataIn[];
+  dataOuts: DataOut[];
+  ifDataOuts: IfDataOut[];
+  properties?: Property[];
+  constraints?: Constraint[];
+  switchData: string;
+  cases: SwitchCase[];
+};
+
 export type Workflow = {
   id: string;
   name: string;
@@ -0,0 +1,56 @@
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use wf_exporter::{
+    dot::{afcl_to_dot, workflow_to_dot},
+    parse_workflow,
+    spec::load_workflow,
+};
+
+const USAGE: &str = "Usage: wf-dot [--afcl] <graph.json | workflow.yaml>
+
+Prints a workflow as Graphviz DOT, e.g. to render it with dot -Tsvg. Saved
+editor graphs (.json) are drawn as in the editor, or as the AFCL they are
+exported to with --afcl. AFCL yaml is always drawn as AFCL.";
+
+fn run(path: &PathBuf, afcl: bool) -> Result<String, String> {
+    if afcl || path.extension().is_some_and(|ext| ext != "json") {
+        return load_workflow(path).map(|workflow| afcl_to_dot(&workflow));
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let workflow = parse_workflow(&content).map_err(|e| e.to_string())?;
+    Ok(workflow_to_dot(&workflow))
+}
+
+fn main() -> ExitCode {
+    let mut afcl = false;
+    let mut inputs = vec![];
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--afcl" => afcl = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("unknown option {}\n\n{}", arg, USAGE);
+                return ExitCode::FAILURE;
+            }
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+    let [path] = inputs.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    match run(path, afcl) {
+        Ok(dot) => {
+            print!("{}", dot);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
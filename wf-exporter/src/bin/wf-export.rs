@@ -0,0 +1,147 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use wf_exporter::{
+    collect_json_files, error::Diagnostics, export_to_yaml, lint::Severity, parse_workflow,
+};
+
+const USAGE: &str = "Usage: wf-export [--stdout] [--explain] <graph.json | directory>...
+
+Exports saved editor graphs to Apollo AFCL yaml. Directories are searched
+recursively for .json files. Every graph is written to a .yaml file next to it,
+or printed to stdout with --stdout. --explain prints how the source of every
+data in- and output was found.";
+
+/// Exports a single graph and returns whether it succeeded.
+fn export_graph(path: &Path, to_stdout: bool, explain: bool) -> bool {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            return false;
+        }
+    };
+
+    let mut diagnostics = if explain {
+        Diagnostics::explaining()
+    } else {
+        Diagnostics::default()
+    };
+    let yaml = match parse_workflow(&content) {
+        Ok(workflow) => export_to_yaml(workflow, &mut diagnostics),
+        Err(e) => {
+            diagnostics.errors.push(e);
+            None
+        }
+    };
+
+    for explanation in diagnostics.explanations.iter().flatten() {
+        eprintln!("{}: explain: {}", path.display(), explanation);
+    }
+    for lint in &diagnostics.lints {
+        let severity = match lint.severity() {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        eprintln!(
+            "{}: {}[{}]: {} (node {})",
+            path.display(),
+            severity,
+            lint.rule.id(),
+            lint,
+            lint.node_id
+        );
+    }
+    for warning in &diagnostics.warnings {
+        eprintln!(
+            "{}: warning: {}{}",
+            path.display(),
+            warning,
+            warning
+                .node_id()
+                .map(|id| format!(" (node {})", id))
+                .unwrap_or_default()
+        );
+    }
+    for error in &diagnostics.errors {
+        eprintln!(
+            "{}: error: {}{}",
+            path.display(),
+            error,
+            error
+                .node_id()
+                .map(|id| format!(" (node {})", id))
+                .unwrap_or_default()
+        );
+    }
+
+    let Some(yaml) = yaml else {
+        return false;
+    };
+    if to_stdout {
+        print!("---\n{}", yaml);
+        return true;
+    }
+
+    let target = path.with_extension("yaml");
+    match fs::write(&target, yaml) {
+        Ok(()) => {
+            eprintln!("{} -> {}", path.display(), target.display());
+            true
+        }
+        Err(e) => {
+            eprintln!("{}: {}", target.display(), e);
+            false
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut to_stdout = false;
+    let mut explain = false;
+    let mut inputs = vec![];
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--stdout" => to_stdout = true,
+            "--explain" => explain = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("unknown option {}\n\n{}", arg, USAGE);
+                return ExitCode::FAILURE;
+            }
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+    if inputs.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    }
+
+    let mut graphs = vec![];
+    for input in &inputs {
+        if let Err(e) = collect_json_files(input, &mut graphs) {
+            eprintln!("{}: {}", input.display(), e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let failed = graphs
+        .iter()
+        .filter(|graph| !export_graph(graph, to_stdout, explain))
+        .count();
+    if failed > 0 {
+        eprintln!(
+            "{} of {} graphs could not be exported",
+            failed,
+            graphs.len()
+        );
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,47 @@
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use wf_exporter::{
+    logs::{overlay, parse_log, Overlay},
+    spec::load_workflow,
+};
+
+const USAGE: &str = "Usage: wf-logs <workflow> <log.json | log.csv>
+
+Reads the function logs of an Apollo run and prints the calls, failures,
+iterations and durations of every node as JSON, keyed by the editor node id of
+a saved editor graph (.json) or the function name of AFCL yaml. The layout of
+the log is described in the documentation of wf_exporter::logs.";
+
+fn run(args: &[String]) -> Result<Overlay, String> {
+    let [workflow, log] = args else {
+        return Err(USAGE.to_string());
+    };
+    let workflow_path = PathBuf::from(workflow);
+    let workflow =
+        load_workflow(&workflow_path).map_err(|e| format!("{}: {}", workflow_path.display(), e))?;
+    let content = fs::read_to_string(log).map_err(|e| format!("{}: {}", log, e))?;
+    let records = parse_log(&content).map_err(|e| format!("{}: {}", log, e))?;
+    Ok(overlay(&workflow, &records))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match run(&args) {
+        Ok(overlay) => {
+            for function in &overlay.unmatched {
+                eprintln!("{} is not part of the workflow", function);
+            }
+            println!("{}", serde_json::to_string_pretty(&overlay).unwrap());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
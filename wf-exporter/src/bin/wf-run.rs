@@ -0,0 +1,118 @@
+use std::{
+    env, fs,
+    io::{self, Read},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use wf_exporter::{
+    interpreter::{Data, Interpreter},
+    runtime::RuntimeConfig,
+    spec::load_workflow,
+};
+
+const USAGE: &str = "Usage: wf-run [--config <runtime.json>] [--workers <n>] [--trace <trace.json>]
+              <workflow> <input.json | ->
+
+Runs a saved editor graph (.json) or AFCL yaml locally and prints the workflow
+outputs as JSON. The input is a JSON object keyed by the names given in the
+start node, - reads it from stdin. Function types listed in the runtime config
+run as local commands or, if built with the wasi feature, as WASI modules. The
+demo types Addition, Subtraction, SumCollection and Collection have built-in
+implementations. --trace writes every function call to a JSON file, also if the
+run fails.";
+
+fn read_input(path: &str) -> Result<Data, String> {
+    let content = if path == "-" {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| e.to_string())?;
+        content
+    } else {
+        fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?
+    };
+    serde_json::from_str(&content).map_err(|e| format!("invalid input: {}", e))
+}
+
+fn run(
+    config: Option<PathBuf>,
+    workers: Option<usize>,
+    trace: Option<PathBuf>,
+    args: &[String],
+) -> Result<Data, String> {
+    let [workflow, input] = args else {
+        return Err(USAGE.to_string());
+    };
+    let workflow_path = PathBuf::from(workflow);
+    let workflow =
+        load_workflow(&workflow_path).map_err(|e| format!("{}: {}", workflow_path.display(), e))?;
+    let input = read_input(input)?;
+
+    let mut interpreter = Interpreter::new();
+    if let Some(config) = config {
+        RuntimeConfig::load(&config)
+            .and_then(|runtime| runtime.configure(&mut interpreter))
+            .map_err(|e| format!("{}: {}", config.display(), e))?;
+    }
+    if let Some(workers) = workers {
+        interpreter.set_workers(workers);
+    }
+    let Some(trace_path) = trace else {
+        return interpreter
+            .run(&workflow, &input)
+            .map_err(|e| e.to_string());
+    };
+    let (result, trace) = interpreter.run_traced(&workflow, &input);
+    fs::write(&trace_path, serde_json::to_string_pretty(&trace).unwrap())
+        .map_err(|e| format!("{}: {}", trace_path.display(), e))?;
+    result.map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let mut config = None;
+    let mut workers = None;
+    let mut trace = None;
+    let mut positional = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            "--config" | "--trace" => match args.next() {
+                Some(path) if arg == "--config" => config = Some(PathBuf::from(path)),
+                Some(path) => trace = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{} needs a file\n\n{}", arg, USAGE);
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--workers" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => workers = Some(n),
+                None => {
+                    eprintln!("--workers needs a number\n\n{}", USAGE);
+                    return ExitCode::FAILURE;
+                }
+            },
+            "-" => positional.push(arg),
+            _ if arg.starts_with('-') => {
+                eprintln!("unknown option {}\n\n{}", arg, USAGE);
+                return ExitCode::FAILURE;
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    match run(config, workers, trace, &positional) {
+        Ok(outputs) => {
+            println!("{}", serde_json::to_string_pretty(&outputs).unwrap());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::{env, path::PathBuf, process::ExitCode};
+
+use wf_exporter::{collect_json_files, spec::run_spec_file};
+
+const USAGE: &str = "Usage: wf-test <spec.json | directory>...
+
+Runs workflow test specs with the local interpreter and mocked functions.
+Directories are searched recursively for .json files. Every case is reported
+as PASS or FAIL, the exit code is non-zero if any case failed.";
+
+fn main() -> ExitCode {
+    let mut inputs = vec![];
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("unknown option {}\n\n{}", arg, USAGE);
+                return ExitCode::FAILURE;
+            }
+            _ => inputs.push(PathBuf::from(arg)),
+        }
+    }
+    if inputs.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    }
+
+    let mut specs = vec![];
+    for input in &inputs {
+        if let Err(e) = collect_json_files(input, &mut specs) {
+            eprintln!("{}: {}", input.display(), e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let (mut passed, mut failed) = (0, 0);
+    for spec in &specs {
+        let results = match run_spec_file(spec) {
+            Ok(results) => results,
+            Err(e) => {
+                println!("FAIL {}: {}", spec.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        for result in results {
+            match &result.failure {
+                None => {
+                    println!("PASS {}: {}", spec.display(), result.name);
+                    passed += 1;
+                }
+                Some(failure) => {
+                    println!("FAIL {}: {}: {}", spec.display(), result.name, failure);
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    println!("{} passed, {} failed", passed, failed);
+    if failed > 0 {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,358 @@
+//! Renders workflows as Graphviz DOT, e.g. for design reviews and documentation. Either the
+//! editor graph or the exported AFCL tree can be rendered, the text does not depend on
+//! anything outside this crate and can be turned into an image with `dot -Tsvg`.
+//!
+//! Compound nodes become clusters holding their children and a node for the compound
+//! itself, which edges into and out of the compound connect to. Control flow is drawn as
+//! solid edges, labelled with the branch they start, data flow as dashed edges labelled
+//! with the names of the data.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    exporter::{ApolloYaml, DataInOrOut, ExportedFunction},
+    EditorNode, Workflow,
+};
+
+/// Renders the editor graph, nodes are keyed by their editor id.
+pub fn workflow_to_dot(workflow: &Workflow) -> String {
+    let nodes = &workflow.data.nodes;
+    let ids: HashSet<&str> = nodes.iter().map(|node| node.id.as_str()).collect();
+    let mut children: HashMap<&str, Vec<&EditorNode>> = HashMap::new();
+    let mut roots = vec![];
+    for node in nodes {
+        match node.parent_node.as_deref().filter(|id| ids.contains(id)) {
+            Some(parent) => children.entry(parent).or_default().push(node),
+            None => roots.push(node),
+        }
+    }
+
+    let mut dot = Dot::new(&workflow.name);
+    for node in roots {
+        editor_node(&mut dot, node, &children);
+    }
+
+    let by_id: HashMap<&str, &EditorNode> =
+        nodes.iter().map(|node| (node.id.as_str(), node)).collect();
+    for edge in &workflow.data.edges {
+        if !ids.contains(edge.source.as_str()) || !ids.contains(edge.target.as_str()) {
+            continue;
+        }
+        let label = by_id
+            .get(edge.source.as_str())
+            .and_then(|source| branch_label(source, &edge.source_handle));
+        dot.control(&edge.source, &edge.target, label.as_deref());
+    }
+    for node in nodes {
+        let data = node.data.data_ins.iter().chain(&node.data.data_outs);
+        for data in data.flatten() {
+            let Some(source) = data.source.as_deref() else {
+                continue;
+            };
+            // a source of the node itself is a constant
+            if source != node.id && ids.contains(source) {
+                let name = data.rename.as_ref().or(data.name.as_ref());
+                dot.data(source, &node.id, name.map_or("", String::as_str));
+            }
+        }
+    }
+    dot.finish()
+}
+
+fn editor_node(dot: &mut Dot, node: &EditorNode, children: &HashMap<&str, Vec<&EditorNode>>) {
+    let kind = match node.typ.as_str() {
+        "parallel" => "parallelFor",
+        "parallelSections" => "parallel",
+        typ => typ,
+    };
+    let nested = children.get(node.id.as_str());
+    if nested.is_none() && !is_compound(kind) {
+        dot.node(&node.id, &node.data.name, shape(kind));
+        return;
+    }
+    dot.open(&node.data.name, kind);
+    dot.node(&node.id, &node.data.name, shape(kind));
+    for child in nested.into_iter().flatten() {
+        editor_node(dot, child, children);
+    }
+    dot.close();
+}
+
+/// Name of the branch an edge leaving `node` at `handle` starts.
+fn branch_label(node: &EditorNode, handle: &str) -> Option<String> {
+    match node.typ.as_str() {
+        "if" if handle == "true" || handle == "false" => Some(handle.to_string()),
+        "switch" if handle == "default" => Some(handle.to_string()),
+        "switch" => node
+            .data
+            .cases
+            .iter()
+            .flatten()
+            .find(|case| case.id == handle)
+            .map(|case| format!("case {}", case.value)),
+        "parallelSections" => node
+            .data
+            .sections
+            .iter()
+            .flatten()
+            .position(|section| section.id == handle)
+            .map(|i| format!("section {}", i + 1)),
+        _ => None,
+    }
+}
+
+/// Renders the AFCL tree. Functions are drawn in the order they run in, connected from the
+/// workflow inputs to its outputs, sub-FCs are drawn next to the workflow body.
+pub fn afcl_to_dot(workflow: &ApolloYaml) -> String {
+    let mut dot = Dot::new(&workflow.name);
+    let mut scope = Scope::default();
+    let start = dot.next_id();
+    let end = dot.next_id();
+    dot.node(&start, &workflow.name, "oval");
+    scope.names.insert(workflow.name.clone(), start.clone());
+    scope.read(&end, workflow.data_outs.as_deref());
+
+    let last = afcl_body(&mut dot, &mut scope, &start, None, &workflow.workflow_body);
+    dot.node(&end, "end", "oval");
+    dot.control(&last, &end, None);
+    scope.resolve(&mut dot);
+
+    for sub_fc in workflow.sub_fcs.iter().flatten() {
+        let mut scope = Scope::default();
+        let header = dot.next_id();
+        dot.open(&sub_fc.name, "subFC");
+        dot.node(&header, &sub_fc.name, shape("subFC"));
+        scope.names.insert(sub_fc.name.clone(), header.clone());
+        scope.read(&header, sub_fc.data_outs.as_deref());
+        afcl_body(&mut dot, &mut scope, &header, None, &sub_fc.body);
+        dot.close();
+        scope.resolve(&mut dot);
+    }
+    dot.finish()
+}
+
+/// Draws `functions` after `previous`, the first one reached by the branch `label`.
+/// Returns the id of the last function drawn, `previous` if there is none.
+fn afcl_body(
+    dot: &mut Dot,
+    scope: &mut Scope,
+    previous: &str,
+    label: Option<&str>,
+    functions: &[ExportedFunction],
+) -> String {
+    let mut previous = previous.to_string();
+    let mut label = label;
+    for function in functions {
+        let (Some(node), Some(kind)) = (function.node(), function.kind()) else {
+            continue;
+        };
+        let id = dot.next_id();
+        scope.names.insert(node.name.clone(), id.clone());
+        scope.read(&id, node.data_ins.as_deref());
+        scope.read(&id, node.data_outs.as_deref());
+        dot.control(&previous, &id, label.take());
+
+        let bodies = branches(function);
+        if bodies.is_empty() {
+            dot.node(&id, &node.name, shape(kind));
+        } else {
+            dot.open(&node.name, kind);
+            dot.node(&id, &node.name, shape(kind));
+            for (branch, body) in bodies {
+                afcl_body(dot, scope, &id, branch.as_deref(), body);
+            }
+            dot.close();
+        }
+        previous = id;
+    }
+    previous
+}
+
+/// The bodies of a compound with the name of the branch each is, `None` for loop bodies.
+fn branches(function: &ExportedFunction) -> Vec<(Option<String>, &[ExportedFunction])> {
+    match function {
+        ExportedFunction::IfThenElse { then, or_else, .. } => vec![
+            (Some("then".to_string()), then.as_slice()),
+            (Some("else".to_string()), or_else.as_slice()),
+        ],
+        ExportedFunction::Switch { cases, default, .. } => cases
+            .iter()
+            .map(|case| {
+                (
+                    Some(format!("case {}", case.value)),
+                    case.functions.as_slice(),
+                )
+            })
+            .chain([(Some("default".to_string()), default.as_slice())])
+            .collect(),
+        ExportedFunction::Parallel { parallel_body, .. } => parallel_body
+            .iter()
+            .enumerate()
+            .map(|(i, section)| {
+                (
+                    Some(format!("section {}", i + 1)),
+                    section.section.as_slice(),
+                )
+            })
+            .collect(),
+        function => function
+            .bodies()
+            .into_iter()
+            .map(|(_, body)| (None, body))
+            .collect(),
+    }
+}
+
+/// Functions of the workflow body or of a sub-FC by name, and the data they read. AFCL
+/// refers to data as `function/data`, so the data edges are drawn once all functions of
+/// the scope are known.
+#[derive(Default)]
+struct Scope {
+    names: HashMap<String, String>,
+    /// `(function, reader id, data name)`
+    reads: Vec<(String, String, String)>,
+}
+
+impl Scope {
+    fn read(&mut self, reader: &str, data: Option<&[DataInOrOut]>) {
+        for data in data.unwrap_or_default() {
+            // merged outputs of if and switch nodes list all of their sources
+            for source in data.source.iter().flat_map(|s| s.split(',')) {
+                if let Some((function, _)) = source.split_once('/') {
+                    self.reads
+                        .push((function.to_string(), reader.to_string(), data.name.clone()));
+                }
+            }
+        }
+    }
+
+    fn resolve(self, dot: &mut Dot) {
+        for (function, reader, name) in &self.reads {
+            match self.names.get(function) {
+                Some(source) if source != reader => dot.data(source, reader, name),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_compound(kind: &str) -> bool {
+    matches!(
+        kind,
+        "if" | "switch" | "parallel" | "parallelFor" | "while" | "for" | "subFC"
+    )
+}
+
+fn shape(kind: &str) -> &'static str {
+    match kind {
+        "start" | "end" => "oval",
+        "if" | "switch" => "diamond",
+        "parallel" | "parallelFor" | "while" | "for" => "hexagon",
+        "subFC" => "component",
+        _ => "box",
+    }
+}
+
+/// Quotes `text` as a DOT id.
+fn quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+/// The DOT text being written. Nodes are written in place, in the clusters open at the
+/// time, edges are collected and written at the end so they do not move nodes.
+struct Dot {
+    text: String,
+    depth: usize,
+    clusters: usize,
+    ids: usize,
+    edges: Vec<String>,
+}
+
+impl Dot {
+    fn new(name: &str) -> Self {
+        let mut text = format!("digraph {} {{\n", quote(name));
+        text.push_str(&format!("  label={};\n", quote(name)));
+        text.push_str("  node [fontname=\"Helvetica\"];\n");
+        text.push_str("  edge [fontname=\"Helvetica\", fontsize=10];\n");
+        Dot {
+            text,
+            depth: 1,
+            clusters: 0,
+            ids: 0,
+            edges: vec![],
+        }
+    }
+
+    /// A fresh node id, for trees whose functions have no id of their own.
+    fn next_id(&mut self) -> String {
+        self.ids += 1;
+        format!("n{}", self.ids)
+    }
+
+    fn line(&mut self, line: &str) {
+        self.text.push_str(&"  ".repeat(self.depth));
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    fn node(&mut self, id: &str, label: &str, shape: &str) {
+        self.line(&format!(
+            "{} [label={}, shape={}];",
+            quote(id),
+            quote(label),
+            shape
+        ));
+    }
+
+    fn open(&mut self, name: &str, kind: &str) {
+        self.clusters += 1;
+        let cluster = format!("subgraph cluster_{} {{", self.clusters);
+        self.line(&cluster);
+        self.depth += 1;
+        self.line(&format!(
+            "label={};",
+            quote(&format!("{} ({})", name, kind))
+        ));
+        self.line("style=rounded;");
+    }
+
+    fn close(&mut self) {
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn control(&mut self, from: &str, to: &str, label: Option<&str>) {
+        let attributes = label
+            .map(|label| format!(" [label={}]", quote(label)))
+            .unwrap_or_default();
+        self.edge(format!("{} -> {}{};", quote(from), quote(to), attributes));
+    }
+
+    fn data(&mut self, from: &str, to: &str, name: &str) {
+        self.edge(format!(
+            "{} -> {} [label={}, style=dashed, color=gray40];",
+            quote(from),
+            quote(to),
+            quote(name)
+        ));
+    }
+
+    fn edge(&mut self, edge: String) {
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    fn finish(mut self) -> String {
+        for edge in std::mem::take(&mut self.edges) {
+            self.line(&edge);
+        }
+        self.text.push_str("}\n");
+        self.text
+    }
+}
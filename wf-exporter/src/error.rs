@@ -0,0 +1,447 @@
+use std::fmt;
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::{
+    explain::Explanation,
+    lint::{Lint, Severity},
+};
+
+/// Problems that make (part of) a workflow impossible to convert.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportError {
+    InvalidInput {
+        reason: String,
+    },
+    MissingStartNode,
+    MissingEndNode,
+    UnknownNodeType {
+        node_id: String,
+        node_type: String,
+    },
+    NodeNotFound {
+        node_id: String,
+    },
+    ParentNotFound {
+        node_id: String,
+        parent_id: String,
+    },
+    SourceNodeNotFound {
+        node_id: String,
+        data_id: String,
+        source: String,
+    },
+    DataOutNotFound {
+        node_id: String,
+        data_id: String,
+        source: String,
+    },
+    MissingSwitchData {
+        node_id: String,
+    },
+    Cycle {
+        node_id: String,
+        names: Vec<String>,
+    },
+    TypeMismatch {
+        node_id: String,
+        data_id: String,
+        expected: String,
+        found: String,
+    },
+    UnknownConditionOperator {
+        node_id: String,
+        operator: String,
+    },
+    UnknownConditionCombinator {
+        node_id: String,
+        combinator: String,
+    },
+    IncompatibleOperator {
+        node_id: String,
+        operator: String,
+        typ: String,
+    },
+    InexpressibleCondition {
+        node_id: String,
+    },
+    UnknownConditionData {
+        node_id: String,
+        reference: String,
+    },
+    ConditionTypeMismatch {
+        node_id: String,
+        reference: String,
+        expected: String,
+        found: String,
+    },
+}
+
+impl ExportError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ExportError::InvalidInput { .. } => "invalidInput",
+            ExportError::MissingStartNode => "missingStartNode",
+            ExportError::MissingEndNode => "missingEndNode",
+            ExportError::UnknownNodeType { .. } => "unknownNodeType",
+            ExportError::NodeNotFound { .. } => "nodeNotFound",
+            ExportError::ParentNotFound { .. } => "parentNotFound",
+            ExportError::SourceNodeNotFound { .. } => "sourceNodeNotFound",
+            ExportError::DataOutNotFound { .. } => "dataOutNotFound",
+            ExportError::MissingSwitchData { .. } => "missingSwitchData",
+            ExportError::Cycle { .. } => "cycle",
+            ExportError::TypeMismatch { .. } => "typeMismatch",
+            ExportError::UnknownConditionOperator { .. } => "unknownConditionOperator",
+            ExportError::UnknownConditionCombinator { .. } => "unknownConditionCombinator",
+            ExportError::IncompatibleOperator { .. } => "incompatibleOperator",
+            ExportError::InexpressibleCondition { .. } => "inexpressibleCondition",
+            ExportError::UnknownConditionData { .. } => "unknownConditionData",
+            ExportError::ConditionTypeMismatch { .. } => "conditionTypeMismatch",
+        }
+    }
+
+    pub fn node_id(&self) -> Option<&str> {
+        match self {
+            ExportError::UnknownNodeType { node_id, .. }
+            | ExportError::NodeNotFound { node_id }
+            | ExportError::ParentNotFound { node_id, .. }
+            | ExportError::SourceNodeNotFound { node_id, .. }
+            | ExportError::DataOutNotFound { node_id, .. }
+            | ExportError::MissingSwitchData { node_id }
+            | ExportError::Cycle { node_id, .. }
+            | ExportError::TypeMismatch { node_id, .. }
+            | ExportError::UnknownConditionOperator { node_id, .. }
+            | ExportError::UnknownConditionCombinator { node_id, .. }
+            | ExportError::IncompatibleOperator { node_id, .. }
+            | ExportError::InexpressibleCondition { node_id }
+            | ExportError::UnknownConditionData { node_id, .. }
+            | ExportError::ConditionTypeMismatch { node_id, .. } => Some(node_id),
+            _ => None,
+        }
+    }
+
+    pub fn data_id(&self) -> Option<&str> {
+        match self {
+            ExportError::SourceNodeNotFound { data_id, .. }
+            | ExportError::DataOutNotFound { data_id, .. }
+            | ExportError::TypeMismatch { data_id, .. } => Some(data_id),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::InvalidInput { reason } => write!(f, "could not parse input: {}", reason),
+            ExportError::MissingStartNode => write!(f, "start node not found"),
+            ExportError::MissingEndNode => write!(f, "end node not found"),
+            ExportError::UnknownNodeType { node_type, .. } => {
+                write!(f, "unknown or unimplemented node type: {}", node_type)
+            }
+            ExportError::NodeNotFound { node_id } => write!(f, "node {} not found", node_id),
+            ExportError::ParentNotFound { parent_id, .. } => {
+                write!(f, "parent node {} not found", parent_id)
+            }
+            ExportError::SourceNodeNotFound { source, .. } => {
+                write!(f, "source node {} of data input not found", source)
+            }
+            ExportError::DataOutNotFound { source, .. } => {
+                write!(f, "node {} has no output matching the data input", source)
+            }
+            ExportError::MissingSwitchData { .. } => {
+                write!(f, "switch node does not evaluate any of its data inputs")
+            }
+            ExportError::Cycle { names, .. } => write!(
+                f,
+                "{} -> {} form a cycle, use a while node to repeat functions",
+                names.join(" -> "),
+                names[0]
+            ),
+            ExportError::TypeMismatch {
+                expected, found, ..
+            } => write!(
+                f,
+                "{} data is connected where {} is expected",
+                found, expected
+            ),
+            ExportError::UnknownConditionOperator { operator, .. } => {
+                write!(f, "unknown condition operator {}", operator)
+            }
+            ExportError::UnknownConditionCombinator { combinator, .. } => write!(
+                f,
+                "conditions are combined with and or or, not with {}",
+                combinator
+            ),
+            ExportError::IncompatibleOperator { operator, typ, .. } => {
+                write!(f, "operator {} cannot compare {} data", operator, typ)
+            }
+            ExportError::InexpressibleCondition { .. } => write!(
+                f,
+                "AFCL combines all conditions with either and or or, conditions mixing both cannot be exported"
+            ),
+            ExportError::UnknownConditionData { reference, .. } => write!(
+                f,
+                "condition reads {}, which is no input of the node, an enclosing compound or the workflow",
+                reference
+            ),
+            ExportError::ConditionTypeMismatch {
+                reference,
+                expected,
+                found,
+                ..
+            } => write!(
+                f,
+                "{} is {} data but the condition compares {}",
+                reference, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl Serialize for ExportError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_diagnostic(
+            serializer,
+            self.kind(),
+            self.node_id(),
+            self.data_id(),
+            &self.to_string(),
+        )
+    }
+}
+
+/// Findings that do not stop the export but likely differ from what the user intended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportWarning {
+    StartNotConnected,
+    EmptyCompound { node_id: String },
+    DuplicateSubFC { node_id: String, name: String },
+    LiteralConditionData { node_id: String, data: String },
+}
+
+impl ExportWarning {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ExportWarning::StartNotConnected => "startNotConnected",
+            ExportWarning::EmptyCompound { .. } => "emptyCompound",
+            ExportWarning::DuplicateSubFC { .. } => "duplicateSubFC",
+            ExportWarning::LiteralConditionData { .. } => "literalConditionData",
+        }
+    }
+
+    pub fn node_id(&self) -> Option<&str> {
+        match self {
+            ExportWarning::EmptyCompound { node_id }
+            | ExportWarning::DuplicateSubFC { node_id, .. }
+            | ExportWarning::LiteralConditionData { node_id, .. } => Some(node_id),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ExportWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportWarning::StartNotConnected => {
+                write!(
+                    f,
+                    "start node has no outgoing edges, workflow body is empty"
+                )
+            }
+            ExportWarning::EmptyCompound { .. } => {
+                write!(f, "compound node has no children and is left out")
+            }
+            ExportWarning::DuplicateSubFC { name, .. } => {
+                write!(
+                    f,
+                    "sub-FC {} is defined more than once, only the first definition is exported",
+                    name
+                )
+            }
+            ExportWarning::LiteralConditionData { data, .. } => write!(
+                f,
+                "condition operand {} names no data and is compared as a literal, set its kind \
+                 to make this explicit",
+                data
+            ),
+        }
+    }
+}
+
+impl Serialize for ExportWarning {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_diagnostic(
+            serializer,
+            self.kind(),
+            self.node_id(),
+            None,
+            &self.to_string(),
+        )
+    }
+}
+
+fn serialize_diagnostic<S: Serializer>(
+    serializer: S,
+    kind: &str,
+    node_id: Option<&str>,
+    data_id: Option<&str>,
+    message: &str,
+) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct("Diagnostic", 4)?;
+    state.serialize_field("kind", kind)?;
+    state.serialize_field("nodeId", &node_id)?;
+    state.serialize_field("dataId", &data_id)?;
+    state.serialize_field("message", message)?;
+    state.end()
+}
+
+/// Everything reported during a conversion. Errors on single data in- or outputs
+/// do not abort the conversion, so all of them can be shown at once.
+#[derive(Debug, Default, Serialize)]
+pub struct Diagnostics {
+    pub errors: Vec<ExportError>,
+    pub warnings: Vec<ExportWarning>,
+    /// Findings of the checks run before the export, see [`crate::lint`].
+    pub lints: Vec<Lint>,
+    /// How the source of every data in- and output was found, only collected for
+    /// diagnostics created with [`Diagnostics::explaining`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanations: Option<Vec<Explanation>>,
+}
+
+impl Diagnostics {
+    /// Diagnostics that also collect [`Explanation`]s, see [`crate::explain`].
+    pub fn explaining() -> Self {
+        Diagnostics {
+            explanations: Some(vec![]),
+            ..Default::default()
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+            || self
+                .lints
+                .iter()
+                .any(|lint| lint.severity() == Severity::Error)
+    }
+}
+
+/// Problems that stop a local run of a workflow, see [`crate::interpreter`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunError {
+    MissingInput { name: String },
+    MissingData { function: String, reference: String },
+    UnknownFunction { function: String, typ: String },
+    FunctionFailed { function: String, reason: String },
+    MissingOutput { function: String, output: String },
+    InvalidData { function: String, reason: String },
+    IterationLimit { function: String, limit: usize },
+    RecursionLimit { function: String },
+}
+
+impl RunError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RunError::MissingInput { .. } => "missingInput",
+            RunError::MissingData { .. } => "missingData",
+            RunError::UnknownFunction { .. } => "unknownFunction",
+            RunError::FunctionFailed { .. } => "functionFailed",
+            RunError::MissingOutput { .. } => "missingOutput",
+            RunError::InvalidData { .. } => "invalidData",
+            RunError::IterationLimit { .. } => "iterationLimit",
+            RunError::RecursionLimit { .. } => "recursionLimit",
+        }
+    }
+
+    /// Name of the function or compound that was running.
+    pub fn function(&self) -> Option<&str> {
+        match self {
+            RunError::MissingInput { .. } => None,
+            RunError::MissingData { function, .. }
+            | RunError::UnknownFunction { function, .. }
+            | RunError::FunctionFailed { function, .. }
+            | RunError::MissingOutput { function, .. }
+            | RunError::InvalidData { function, .. }
+            | RunError::IterationLimit { function, .. }
+            | RunError::RecursionLimit { function } => Some(function),
+        }
+    }
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::MissingInput { name } => write!(f, "workflow input {} is missing", name),
+            RunError::MissingData {
+                function,
+                reference,
+            } => write!(f, "{} reads {}, which has no value", function, reference),
+            RunError::UnknownFunction { function, typ } => write!(
+                f,
+                "no implementation for type {} of function {}",
+                typ, function
+            ),
+            RunError::FunctionFailed { function, reason } => {
+                write!(f, "{} failed: {}", function, reason)
+            }
+            RunError::MissingOutput { function, output } => {
+                write!(f, "{} did not return {}", function, output)
+            }
+            RunError::InvalidData { function, reason } => write!(f, "{}: {}", function, reason),
+            RunError::IterationLimit { function, limit } => {
+                write!(f, "{} did not finish within {} iterations", function, limit)
+            }
+            RunError::RecursionLimit { function } => write!(
+                f,
+                "sub-FC calls of {} are nested too deep, a sub-FC probably calls itself",
+                function
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+/// Problems reading a log of an Apollo run, see [`crate::logs`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogError {
+    InvalidJson {
+        reason: String,
+    },
+    InvalidCsv {
+        reason: String,
+    },
+    /// A record ending before it started. `record` counts from 1.
+    NegativeDuration {
+        function: String,
+        record: usize,
+    },
+}
+
+impl LogError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LogError::InvalidJson { .. } => "invalidJson",
+            LogError::InvalidCsv { .. } => "invalidCsv",
+            LogError::NegativeDuration { .. } => "negativeDuration",
+        }
+    }
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogError::InvalidJson { reason } => write!(f, "invalid JSON log: {}", reason),
+            LogError::InvalidCsv { reason } => write!(f, "invalid CSV log: {}", reason),
+            LogError::NegativeDuration { function, record } => {
+                write!(f, "record {} of {} ends before it starts", record, function)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LogError {}
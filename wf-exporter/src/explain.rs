@@ -0,0 +1,151 @@
+//! Explains how the exporter found the `source` of every data in- and output: which rule
+//! fired and which nodes of the graph it looked at. Collected if the export is run with
+//! [`Diagnostics::explaining`](crate::error::Diagnostics::explaining).
+
+use std::fmt;
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::exporter::Node;
+
+/// The rules deciding the source of a data in- or output, in the order they are tried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SourceRule {
+    /// Not connected: an output of the node or a workflow input named by its start source.
+    Unconnected,
+    /// A value typed into the node.
+    Constant,
+    /// An input of the parallel for around the node, the element of an iterated collection.
+    ParallelForInput,
+    /// A data loop of a for node.
+    DataLoop,
+    /// An output of an if or switch node, merged from its branches.
+    BranchOutput,
+    /// An output of another node, or of the compound around it next to the reader.
+    Output,
+    /// An input of the while, for or sub-FC around the node, named like the compound input.
+    EnclosingInput,
+    /// An output of an if or switch node, the sources of all branches joined.
+    MergedBranches,
+}
+
+impl SourceRule {
+    pub fn id(&self) -> &'static str {
+        match self {
+            SourceRule::Unconnected => "unconnected",
+            SourceRule::Constant => "constant",
+            SourceRule::ParallelForInput => "parallelForInput",
+            SourceRule::DataLoop => "dataLoop",
+            SourceRule::BranchOutput => "branchOutput",
+            SourceRule::Output => "output",
+            SourceRule::EnclosingInput => "enclosingInput",
+            SourceRule::MergedBranches => "mergedBranches",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    In,
+    Out,
+}
+
+/// Why a node was looked at while resolving a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Role {
+    /// The compound around the reader.
+    Parent,
+    /// The node the data comes from in the graph.
+    Source,
+    /// A compound around the source, climbed until it is next to the reader.
+    SourceParent,
+    /// The compound around the reader whose input the data is renamed to.
+    Enclosing,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Visit {
+    pub node_id: String,
+    pub name: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub node_id: String,
+    pub node: String,
+    pub data_id: String,
+    pub direction: Direction,
+    /// The exported name and source.
+    pub name: String,
+    pub source: Option<String>,
+    pub rule: SourceRule,
+    /// In the order they were looked at.
+    pub visited: Vec<Visit>,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.direction {
+            Direction::In => "input",
+            Direction::Out => "output",
+        };
+        write!(
+            f,
+            "{} {} {} <- {} by {}",
+            self.node,
+            direction,
+            self.name,
+            self.source.as_deref().unwrap_or("(none)"),
+            self.rule.id()
+        )?;
+        for (i, visit) in self.visited.iter().enumerate() {
+            let role = match visit.role {
+                Role::Parent => "parent",
+                Role::Source => "source",
+                Role::SourceParent => "source parent",
+                Role::Enclosing => "enclosing",
+            };
+            let separator = if i == 0 { ", visited" } else { "," };
+            write!(f, "{} {} {}", separator, role, visit.name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Explanation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Explanation", 9)?;
+        state.serialize_field("nodeId", &self.node_id)?;
+        state.serialize_field("node", &self.node)?;
+        state.serialize_field("dataId", &self.data_id)?;
+        state.serialize_field("direction", &self.direction)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("source", &self.source)?;
+        state.serialize_field("rule", self.rule.id())?;
+        state.serialize_field("visited", &self.visited)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+/// What resolving a single source fired and looked at.
+#[derive(Default)]
+pub(crate) struct Trail {
+    pub(crate) rule: Option<SourceRule>,
+    pub(crate) visited: Vec<Visit>,
+}
+
+impl Trail {
+    pub(crate) fn visit(&mut self, node: &Node, role: Role) {
+        self.visited.push(Visit {
+            node_id: node.editor_id().unwrap_or_default().to_string(),
+            name: node.name.clone(),
+            role,
+        });
+    }
+}
@@ -1,87 +1,142 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
 
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
+use serde_yaml::Value;
 
 use crate::{
-    IfDataOut, InternalDataInOrOut, InternalIterator, InternalLoopCounter, PropertyOrConstraint,
-    Workflow,
+    error::{Diagnostics, ExportError, ExportWarning},
+    explain::{Direction, Explanation, Role, SourceRule, Trail},
+    utils::{deserialize_bool, deserialize_optional_scalar, deserialize_scalar},
+    Condition as EditorCondition, ConditionGroup, ConditionTerm, Conditions, IfDataOut,
+    InternalDataInOrOut, InternalDataLoop, InternalIterator, InternalLoopCounter, InternalSection,
+    OperandKind, Position, PropertyOrConstraint, SwitchCase, Workflow,
 };
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ApolloYaml {
-    name: String,
-    #[serde(rename = "subFCs")]
-    sub_fcs: Option<Vec<SubFC>>,
+    /// Editor nodes the workflow data in- and outputs come from.
+    #[serde(skip)]
+    start_id: String,
+    #[serde(skip)]
+    end_id: String,
+
+    pub(crate) name: String,
+    #[serde(
+        rename = "subFCs",
+        default,
+        with = "serde_yaml::with::singleton_map_recursive"
+    )]
+    pub(crate) sub_fcs: Option<Vec<SubFC>>,
     #[serde(rename = "dataIns")]
-    data_ins: Option<Vec<DataInOrOut>>,
+    pub(crate) data_ins: Option<Vec<DataInOrOut>>,
     #[serde(
         with = "serde_yaml::with::singleton_map_recursive",
         rename = "workflowBody"
     )]
-    workflow_body: Vec<ExportedFunction>,
+    pub(crate) workflow_body: Vec<ExportedFunction>,
     #[serde(rename = "dataOuts")]
-    data_outs: Option<Vec<DataInOrOut>>,
+    pub(crate) data_outs: Option<Vec<DataInOrOut>>,
 }
 
+/// A reusable workflow fragment, called like a function whose type is the sub-FC name.
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone)]
-struct SubFC {
-    name: String,
-    sub_fc_body: Node,
-    data_ins: Vec<DataInOrOut>,
-    data_outs: Vec<DataInOrOut>,
-    workflow_body: Vec<Function>,
-    properties: Vec<PropertyOrConstraint>,
-    constraints: Vec<PropertyOrConstraint>,
+pub(crate) struct SubFC {
+    /// Editor node defining the sub-FC.
+    #[serde(skip)]
+    id: String,
+
+    pub(crate) name: String,
+    #[serde(rename = "dataIns")]
+    pub(crate) data_ins: Option<Vec<DataInOrOut>>,
+    #[serde(rename = "subFCBody", default)]
+    pub(crate) body: Vec<ExportedFunction>,
+    #[serde(rename = "dataOuts")]
+    pub(crate) data_outs: Option<Vec<DataInOrOut>>,
+    pub(crate) properties: Option<Vec<PropertyOrConstraint>>,
+    pub(crate) constraints: Option<Vec<PropertyOrConstraint>>,
 }
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone)]
-struct DataInOrOut {
+pub(crate) struct DataInOrOut {
     #[serde(skip)]
     id: String,
-    name: String,
+    pub(crate) name: String,
     #[serde(rename = "type")]
-    typ: String,
-    source: Option<String>,
-    properties: Option<Vec<PropertyOrConstraint>>,
-    constraints: Option<Vec<PropertyOrConstraint>>,
+    pub(crate) typ: String,
+    #[serde(default, deserialize_with = "deserialize_optional_scalar")]
+    pub(crate) source: Option<String>,
+    pub(crate) properties: Option<Vec<PropertyOrConstraint>>,
+    pub(crate) constraints: Option<Vec<PropertyOrConstraint>>,
+}
+
+impl SubFC {
+    /// Id of the node defining the sub-FC, `None` for sub-FCs read from AFCL.
+    pub(crate) fn editor_id(&self) -> Option<&str> {
+        Some(self.id.as_str()).filter(|id| !id.is_empty())
+    }
+}
+
+impl DataInOrOut {
+    /// Id of the data in the editor graph, `None` for data read from AFCL.
+    pub(crate) fn editor_id(&self) -> Option<&str> {
+        Some(self.id.as_str()).filter(|id| !id.is_empty())
+    }
 }
 
 fn skip_type_if(typ: &String) -> bool {
-    typ == "if" || typ == "parallel" || typ == "while"
+    typ == "if"
+        || typ == "switch"
+        || typ == "parallel"
+        || typ == "parallelSections"
+        || typ == "while"
+        || typ == "for"
 }
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone)]
-struct Node {
+pub(crate) struct Node {
     #[serde(skip)]
     id: String,
     #[serde(skip)]
     parent_id: Option<String>,
+    #[serde(skip)]
+    position: Position,
 
     #[serde(skip)]
     internal_data_ins: Option<Vec<InternalDataInOrOut>>,
     #[serde(skip)]
     internal_data_outs: Option<Vec<InternalDataInOrOut>>,
 
-    name: String,
-    #[serde(rename = "type", skip_serializing_if = "skip_type_if")]
-    typ: String,
+    pub(crate) name: String,
+    #[serde(rename = "type", default, skip_serializing_if = "skip_type_if")]
+    pub(crate) typ: String,
     #[serde(rename = "dataIns")]
-    data_ins: Option<Vec<DataInOrOut>>,
+    pub(crate) data_ins: Option<Vec<DataInOrOut>>,
     #[serde(rename = "dataOuts")]
-    data_outs: Option<Vec<DataInOrOut>>,
-    properties: Option<Vec<PropertyOrConstraint>>,
-    constraints: Option<Vec<PropertyOrConstraint>>,
-    #[serde(skip_serializing)]
+    pub(crate) data_outs: Option<Vec<DataInOrOut>>,
+    pub(crate) properties: Option<Vec<PropertyOrConstraint>>,
+    pub(crate) constraints: Option<Vec<PropertyOrConstraint>>,
+    #[serde(skip)]
     function: Function,
 }
 
+impl Node {
+    /// Id of the node in the editor graph, `None` for nodes read from AFCL.
+    pub(crate) fn editor_id(&self) -> Option<&str> {
+        Some(self.id.as_str()).filter(|id| !id.is_empty())
+    }
+}
+
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone)]
-enum ExportedFunction {
+pub(crate) enum ExportedFunction {
     #[serde(rename = "function")]
     AtomicFunction {
         #[serde(flatten)]
@@ -92,12 +147,29 @@ enum ExportedFunction {
         #[serde(flatten)]
         node: Node,
         condition: Vec<Condition>,
+        #[serde(default)]
         then: Vec<ExportedFunction>,
-        #[serde(rename = "else")]
+        #[serde(rename = "else", default)]
         or_else: Vec<ExportedFunction>,
     },
-    // Switch {
-    // },
+    #[serde(rename = "switch")]
+    Switch {
+        #[serde(flatten)]
+        node: Node,
+        #[serde(rename = "dataEval")]
+        data_eval: DataInOrOut,
+        #[serde(default)]
+        cases: Vec<Case>,
+        #[serde(default)]
+        default: Vec<ExportedFunction>,
+    },
+    #[serde(rename = "parallel")]
+    Parallel {
+        #[serde(flatten)]
+        node: Node,
+        #[serde(rename = "parallelBody")]
+        parallel_body: Vec<Section>,
+    },
     #[serde(rename = "parallelFor")]
     ParallelFor {
         #[serde(flatten)]
@@ -116,30 +188,139 @@ enum ExportedFunction {
         #[serde(rename = "loopBody")]
         loop_body: Vec<ExportedFunction>,
     },
+    #[serde(rename = "for")]
     SequentialFor {
         #[serde(flatten)]
         node: Node,
-        data_loops: Vec<DataLoop>,
+        #[serde(rename = "loopCounter")]
         loop_counter: LoopCounter,
+        #[serde(rename = "dataLoops")]
+        data_loops: Option<Vec<DataLoop>>,
         #[serde(rename = "loopBody")]
         loop_body: Vec<ExportedFunction>,
     },
     StartOrEnd,
 }
 
+impl ExportedFunction {
+    /// The key the function is written under in AFCL, `None` for the start and end of a flow.
+    pub(crate) fn kind(&self) -> Option<&'static str> {
+        match self {
+            ExportedFunction::AtomicFunction { .. } => Some("function"),
+            ExportedFunction::IfThenElse { .. } => Some("if"),
+            ExportedFunction::Switch { .. } => Some("switch"),
+            ExportedFunction::Parallel { .. } => Some("parallel"),
+            ExportedFunction::ParallelFor { .. } => Some("parallelFor"),
+            ExportedFunction::SequentialWhile { .. } => Some("while"),
+            ExportedFunction::SequentialFor { .. } => Some("for"),
+            ExportedFunction::StartOrEnd => None,
+        }
+    }
+
+    /// `None` for the start and end of a flow.
+    pub(crate) fn node(&self) -> Option<&Node> {
+        match self {
+            ExportedFunction::AtomicFunction { node }
+            | ExportedFunction::IfThenElse { node, .. }
+            | ExportedFunction::Switch { node, .. }
+            | ExportedFunction::Parallel { node, .. }
+            | ExportedFunction::ParallelFor { node, .. }
+            | ExportedFunction::SequentialWhile { node, .. }
+            | ExportedFunction::SequentialFor { node, .. } => Some(node),
+            ExportedFunction::StartOrEnd => None,
+        }
+    }
+
+    /// Functions nested in a compound: its branches, cases, sections or loop body in order,
+    /// each with its path relative to the function, like `cases[1].functions`.
+    pub(crate) fn bodies(&self) -> Vec<(String, &[ExportedFunction])> {
+        match self {
+            ExportedFunction::AtomicFunction { .. } | ExportedFunction::StartOrEnd => vec![],
+            ExportedFunction::IfThenElse { then, or_else, .. } => {
+                vec![("then".to_string(), then), ("else".to_string(), or_else)]
+            }
+            ExportedFunction::Switch { cases, default, .. } => cases
+                .iter()
+                .enumerate()
+                .map(|(i, case)| (format!("cases[{}].functions", i), case.functions.as_slice()))
+                .chain([("default".to_string(), default.as_slice())])
+                .collect(),
+            ExportedFunction::Parallel { parallel_body, .. } => parallel_body
+                .iter()
+                .enumerate()
+                .map(|(i, section)| {
+                    (
+                        format!("parallelBody[{}].section", i),
+                        section.section.as_slice(),
+                    )
+                })
+                .collect(),
+            ExportedFunction::ParallelFor { loop_body, .. }
+            | ExportedFunction::SequentialWhile { loop_body, .. }
+            | ExportedFunction::SequentialFor { loop_body, .. } => {
+                vec![("loopBody".to_string(), loop_body)]
+            }
+        }
+    }
+}
+
+impl ApolloYaml {
+    /// Id of the start node in the editor graph, `None` for workflows read from AFCL.
+    pub(crate) fn start_id(&self) -> Option<&str> {
+        Some(self.start_id.as_str()).filter(|id| !id.is_empty())
+    }
+
+    pub(crate) fn end_id(&self) -> Option<&str> {
+        Some(self.end_id.as_str()).filter(|id| !id.is_empty())
+    }
+
+    /// Nodes of the workflow body and then of the sub-FCs, every compound before the
+    /// functions in it.
+    pub(crate) fn nodes(&self) -> Vec<&Node> {
+        let mut nodes = vec![];
+        collect_nodes(&self.workflow_body, &mut nodes);
+        for sub_fc in self.sub_fcs.iter().flatten() {
+            collect_nodes(&sub_fc.body, &mut nodes);
+        }
+        nodes
+    }
+}
+
+pub(crate) fn collect_nodes<'a>(functions: &'a [ExportedFunction], nodes: &mut Vec<&'a Node>) {
+    for function in functions {
+        nodes.extend(function.node());
+        for (_, body) in function.bodies() {
+            collect_nodes(body, nodes);
+        }
+    }
+}
+
 #[skip_serializing_none]
-#[derive(Serialize, Deserialize, Clone)]
+#[allow(clippy::enum_variant_names)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 enum Function {
+    #[default]
     #[serde(rename = "function")]
     AtomicFunction,
+    /// Definition of a sub-FC, exported as a call at its place in the flow.
+    SubFC,
     IfThenElse {
         condition: Vec<Condition>,
 
         #[serde(skip)]
         if_data_outs: Option<Vec<IfDataOut>>,
     },
-    // Switch {
-    // },
+    Switch {
+        /// Id of the evaluated data input.
+        data_eval: Option<String>,
+        cases: Vec<SwitchCase>,
+
+        #[serde(skip)]
+        if_data_outs: Option<Vec<IfDataOut>>,
+    },
+    Parallel {
+        sections: Vec<InternalSection>,
+    },
     ParallelFor {
         iterators: Option<Vec<String>>,
         loop_counter: Option<InternalLoopCounter>,
@@ -149,102 +330,388 @@ enum Function {
         condition: Vec<Condition>,
     },
     SequentialFor {
-        data_loops: Vec<DataLoop>,
-        loop_counter: LoopCounter,
+        data_loops: Vec<InternalDataLoop>,
+        loop_counter: InternalLoopCounter,
     },
     StartOrEnd,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
-struct DataLoop {
-    name: String,
-    #[serde(rename = "type")]
-    typ: String,
-    init_source: String,
-    loop_source: String,
-    value: String,
-    properties: Vec<PropertyOrConstraint>,
-    constraints: Vec<PropertyOrConstraint>,
+pub(crate) struct Case {
+    #[serde(deserialize_with = "deserialize_scalar")]
+    pub(crate) value: String,
+    #[serde(default)]
+    pub(crate) functions: Vec<ExportedFunction>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct Section {
+    #[serde(default)]
+    pub(crate) section: Vec<ExportedFunction>,
 }
 
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone)]
-struct LoopCounter {
-    name: Option<String>,
+pub(crate) struct DataLoop {
+    pub(crate) name: String,
     #[serde(rename = "type")]
-    typ: String,
-    from: Option<String>,
-    to: String,
-    step: String,
+    pub(crate) typ: String,
+    #[serde(
+        rename = "initSource",
+        default,
+        deserialize_with = "deserialize_optional_scalar"
+    )]
+    pub(crate) init_source: Option<String>,
+    #[serde(rename = "loopSource")]
+    pub(crate) loop_source: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_scalar")]
+    pub(crate) value: Option<String>,
+    pub(crate) properties: Option<Vec<PropertyOrConstraint>>,
+    pub(crate) constraints: Option<Vec<PropertyOrConstraint>>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone)]
-struct Condition {
-    data1: String,
-    data2: String,
+pub(crate) struct LoopCounter {
+    pub(crate) name: Option<String>,
     #[serde(rename = "type")]
-    typ: String,
-    operator: String,
-    // operator: ConditionOperator,
-    negation: String,
-    // combine_with: CombineWith,
-    #[serde(rename = "combinedWith")]
-    combined_with: String,
+    pub(crate) typ: String,
+    #[serde(default, deserialize_with = "deserialize_optional_scalar")]
+    pub(crate) from: Option<String>,
+    #[serde(deserialize_with = "deserialize_scalar")]
+    pub(crate) to: String,
+    #[serde(deserialize_with = "deserialize_scalar")]
+    pub(crate) step: String,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
-enum CombineWith {
+pub(crate) struct Condition {
+    /// A reference like `node/output` or a literal, written as number or boolean if the
+    /// condition compares numbers or booleans.
+    pub(crate) data1: Value,
+    pub(crate) data2: Value,
+    #[serde(rename = "type")]
+    pub(crate) typ: String,
+    pub(crate) operator: ConditionOperator,
+    /// AFCL defaults to no negation and `and` when these are left out.
+    #[serde(default, deserialize_with = "deserialize_bool")]
+    pub(crate) negation: bool,
+    #[serde(rename = "combinedWith", default)]
+    pub(crate) combined_with: CombineWith,
+    /// Operands naming data, checked once all nodes are known.
+    #[serde(skip)]
+    pub(crate) references: Vec<String>,
+    /// Operands of no set kind that look like references, literals if they name no data.
+    #[serde(skip)]
+    pub(crate) guessed_references: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CombineWith {
+    #[default]
     And,
     Or,
 }
-#[derive(Serialize, Deserialize, Clone)]
-enum ConditionOperator {
+
+impl CombineWith {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            CombineWith::And => "and",
+            CombineWith::Or => "or",
+        }
+    }
+
+    fn parse(combinator: &str) -> Option<Self> {
+        [CombineWith::And, CombineWith::Or]
+            .iter()
+            .copied()
+            .find(|c| c.as_str() == combinator)
+    }
+}
+
+/// Operators offered by the editor, see `conditionTypes` in the frontend.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub(crate) enum ConditionOperator {
+    #[serde(rename = "==")]
     Eq,
+    #[serde(rename = "!=")]
     Neq,
+    #[serde(rename = ">")]
     Gt,
+    #[serde(rename = "<")]
     Lt,
+    #[serde(rename = ">=")]
     Gte,
+    #[serde(rename = "<=")]
     Lte,
+    #[serde(rename = "contains")]
     Contains,
+    #[serde(rename = "startsWith")]
     StartsWith,
+    #[serde(rename = "endsWith")]
     EndsWith,
 }
 
+impl ConditionOperator {
+    const ALL: [ConditionOperator; 9] = [
+        ConditionOperator::Eq,
+        ConditionOperator::Neq,
+        ConditionOperator::Gt,
+        ConditionOperator::Lt,
+        ConditionOperator::Gte,
+        ConditionOperator::Lte,
+        ConditionOperator::Contains,
+        ConditionOperator::StartsWith,
+        ConditionOperator::EndsWith,
+    ];
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ConditionOperator::Eq => "==",
+            ConditionOperator::Neq => "!=",
+            ConditionOperator::Gt => ">",
+            ConditionOperator::Lt => "<",
+            ConditionOperator::Gte => ">=",
+            ConditionOperator::Lte => "<=",
+            ConditionOperator::Contains => "contains",
+            ConditionOperator::StartsWith => "startsWith",
+            ConditionOperator::EndsWith => "endsWith",
+        }
+    }
+
+    fn parse(operator: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|o| o.as_str() == operator)
+    }
+
+    /// Whether the operator can compare data of type `typ`. Equality works for every
+    /// type, `contains` also for collections.
+    fn applies_to(&self, typ: &str) -> bool {
+        match self {
+            ConditionOperator::Eq | ConditionOperator::Neq => true,
+            ConditionOperator::Gt
+            | ConditionOperator::Lt
+            | ConditionOperator::Gte
+            | ConditionOperator::Lte => typ == "number",
+            ConditionOperator::Contains => typ == "string" || is_collection(typ),
+            ConditionOperator::StartsWith | ConditionOperator::EndsWith => typ == "string",
+        }
+    }
+}
+
+/// Returns the node directly inside `parent` that is or contains `node`.
+fn scope_ancestor<'a>(
+    node: &'a Node,
+    parent: &Option<String>,
+    node_map: &'a HashMap<String, Node>,
+) -> Option<&'a Node> {
+    let mut current = node;
+    while current.parent_id != *parent {
+        current = node_map.get(current.parent_id.as_ref()?)?;
+    }
+    Some(current)
+}
+
+/// Dependencies between the nodes directly inside `parent`, successors in editor order.
+/// Edges of nested nodes count as edges of the enclosing node in the scope.
+fn scope_successors(
+    node_map: &HashMap<String, Node>,
+    edge_map: &HashMap<String, Vec<&Node>>,
+    parent: &Option<String>,
+) -> HashMap<String, Vec<String>> {
+    let mut successors: HashMap<String, Vec<String>> = HashMap::new();
+    for (source, targets) in edge_map {
+        // branch edges are keyed by node and handle and stay inside their compound
+        let Some(source_node) = node_map.get(source) else {
+            continue;
+        };
+        let Some(source) = scope_ancestor(source_node, parent, node_map) else {
+            continue;
+        };
+        for target_node in targets {
+            let Some(target) = scope_ancestor(target_node, parent, node_map) else {
+                continue;
+            };
+            // edges between a compound and its own children are no dependencies
+            let is_self_loop = target_node.id == source_node.id;
+            let entry = successors.entry(source.id.clone()).or_default();
+            if (target.id != source.id || is_self_loop) && !entry.contains(&target.id) {
+                entry.push(target.id.clone());
+            }
+        }
+    }
+    for targets in successors.values_mut() {
+        targets.sort_by(|a, b| editor_order(&node_map[a], &node_map[b]));
+    }
+    successors
+}
+
+/// Finds the cycles in every scope of the graph. Each cycle is reported once, as the
+/// nodes along it in order.
+fn find_cycles<'a>(
+    node_map: &'a HashMap<String, Node>,
+    edge_map: &HashMap<String, Vec<&Node>>,
+    children_map: &HashMap<String, Vec<Node>>,
+) -> Vec<Vec<&'a Node>> {
+    fn visit(
+        node: &str,
+        successors: &HashMap<String, Vec<String>>,
+        stack: &mut Vec<String>,
+        done: &mut HashSet<String>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        stack.push(node.to_string());
+        for target in successors.get(node).into_iter().flatten() {
+            if let Some(pos) = stack.iter().position(|n| n == target) {
+                let cycle = stack[pos..].to_vec();
+                let known = cycles
+                    .iter()
+                    .any(|c| c.len() == cycle.len() && cycle.iter().all(|n| c.contains(n)));
+                if !known {
+                    cycles.push(cycle);
+                }
+            } else if !done.contains(target) {
+                visit(target, successors, stack, done, cycles);
+            }
+        }
+        stack.pop();
+        done.insert(node.to_string());
+    }
+
+    let mut scopes: Vec<Option<String>> = vec![None];
+    scopes.extend(children_map.keys().cloned().map(Some));
+    scopes.sort();
+
+    let mut cycles = vec![];
+    for parent in scopes {
+        let successors = scope_successors(node_map, edge_map, &parent);
+        let mut nodes: Vec<&Node> = node_map
+            .values()
+            .filter(|n| n.parent_id == parent)
+            .collect();
+        nodes.sort_by(|a, b| editor_order(a, b));
+
+        let mut done = HashSet::new();
+        let mut scope_cycles = vec![];
+        for node in nodes {
+            if !done.contains(&node.id) {
+                visit(
+                    &node.id,
+                    &successors,
+                    &mut vec![],
+                    &mut done,
+                    &mut scope_cycles,
+                );
+            }
+        }
+        cycles.extend(
+            scope_cycles
+                .into_iter()
+                .map(|c| c.iter().map(|id| &node_map[id]).collect()),
+        );
+    }
+    cycles
+}
+
+/// Collects the nodes directly inside `parent` that are reachable from `entries`, in
+/// editor order, together with the dependencies between them.
+fn collect_scope<'a>(
+    entries: &[&Node],
+    node_map: &'a HashMap<String, Node>,
+    edge_map: &HashMap<String, Vec<&Node>>,
+    parent: &Option<String>,
+    scheduled: &HashSet<String>,
+) -> (Vec<&'a Node>, HashMap<String, Vec<String>>) {
+    let mut successors = scope_successors(node_map, edge_map, parent);
+
+    let mut scope: Vec<&Node> = vec![];
+    let mut queue: Vec<&Node> = entries
+        .iter()
+        .filter_map(|n| scope_ancestor(node_map.get(&n.id)?, parent, node_map))
+        .collect();
+    while let Some(node) = queue.pop() {
+        if scheduled.contains(&node.id) || scope.iter().any(|n| n.id == node.id) {
+            continue;
+        }
+        scope.push(node);
+        for target in successors.get(&node.id).into_iter().flatten() {
+            queue.extend(node_map.get(target));
+        }
+    }
+    scope.sort_by(|a, b| editor_order(a, b));
+    successors.retain(|source, _| scope.iter().any(|n| n.id == *source));
+    (scope, successors)
+}
+
+/// Exports the nodes inside `calling_parent` reachable from `nodes`. A node is only
+/// emitted after all of its predecessors in the same scope, ties are broken by editor
+/// order.
 fn parse_sub_flow(
     nodes: &Vec<&Node>,
     node_map: &HashMap<String, Node>,
     edge_map: &HashMap<String, Vec<&Node>>,
     children_map: &HashMap<String, Vec<Node>>,
-    dependency_count_map: &mut HashMap<String, usize>,
+    scheduled: &mut HashSet<String>,
     calling_parent: Option<String>,
+    diagnostics: &mut Diagnostics,
 ) -> Vec<ExportedFunction> {
     let mut res = Vec::new();
-    for node in nodes {
-        if let Some(count) = dependency_count_map.get_mut(&node.id) {
-            if *count > 0 || node.parent_id != calling_parent {
-                continue;
-            } else {
-                *count += 1;
+    let (mut remaining, successors) =
+        collect_scope(nodes, node_map, edge_map, &calling_parent, scheduled);
+    let mut in_degree: HashMap<&str, usize> = HashMap::new();
+    for target in successors.values().flatten() {
+        *in_degree.entry(target).or_default() += 1;
+    }
+
+    while let Some(next) = remaining
+        .iter()
+        .position(|n| in_degree.get(n.id.as_str()).is_none_or(|d| *d == 0))
+    {
+        let node = remaining.remove(next);
+        for target in successors.get(&node.id).into_iter().flatten() {
+            if let Some(degree) = in_degree.get_mut(target.as_str()) {
+                *degree -= 1;
             }
         }
-        let new_node = Node {
-            data_ins: node.internal_data_ins.as_ref().map(|d| {
-                d.iter()
-                    .map(|d| get_data_input(d, node.id.clone(), &node_map, &edge_map))
-                    .collect()
-            }),
+        scheduled.insert(node.id.clone());
 
-            data_outs: node.internal_data_outs.as_ref().map(|d| {
-                d.iter()
-                    .map(|d| get_data_input(d, node.id.clone(), &node_map, &edge_map))
-                    .collect()
-            }),
+        let new_node = Node {
+            data_ins: resolve_data(
+                &node.internal_data_ins,
+                &node.id,
+                Direction::In,
+                node_map,
+                edge_map,
+                diagnostics,
+            ),
+            data_outs: resolve_data(
+                &node.internal_data_outs,
+                &node.id,
+                Direction::Out,
+                node_map,
+                edge_map,
+                diagnostics,
+            ),
             ..(*node).clone()
         };
         match node.function.clone() {
             Function::AtomicFunction => {
                 res.push(ExportedFunction::AtomicFunction { node: new_node });
             }
+            Function::SubFC => {
+                // outputs of a call are produced by the sub-FC, not by its body
+                let data_outs = new_node.data_outs.clone().map(|outs| {
+                    outs.into_iter()
+                        .map(|d| DataInOrOut { source: None, ..d })
+                        .collect()
+                });
+                res.push(ExportedFunction::AtomicFunction {
+                    node: Node {
+                        data_outs,
+                        ..new_node
+                    },
+                });
+            }
             Function::IfThenElse {
                 condition,
                 if_data_outs,
@@ -259,8 +726,9 @@ fn parse_sub_flow(
                         node_map,
                         edge_map,
                         children_map,
-                        dependency_count_map,
+                        scheduled,
                         Some(node.id.clone()),
+                        diagnostics,
                     ));
                 }
 
@@ -270,44 +738,19 @@ fn parse_sub_flow(
                         node_map,
                         edge_map,
                         children_map,
-                        dependency_count_map,
+                        scheduled,
                         Some(node.id.clone()),
+                        diagnostics,
                     ));
                 }
                 res.push(ExportedFunction::IfThenElse {
                     node: Node {
-                        data_outs: if_data_outs.map(|list| {
-                            list.iter()
-                                .map(|internal_out| {
-                                    let data_in_out = new_node.data_outs.clone().unwrap_or(vec![]);
-
-                                    let sources: Vec<&DataInOrOut> = data_in_out
-                                        .iter()
-                                        .filter(|dio| internal_out.sources.contains(&dio.id))
-                                        .collect();
-
-                                    DataInOrOut {
-                                        id: internal_out.id.clone(),
-                                        name: internal_out.name.clone(),
-                                        typ: sources
-                                            .get(0)
-                                            .map(|s| s.typ.clone())
-                                            .unwrap_or("string".to_string()),
-                                        source: Some(
-                                            sources
-                                                .iter()
-                                                .map(|dio| {
-                                                    dio.source.clone().unwrap_or("".to_string())
-                                                })
-                                                .collect::<Vec<String>>()
-                                                .join(","),
-                                        ),
-                                        properties: None,
-                                        constraints: None,
-                                    }
-                                })
-                                .collect()
-                        }),
+                        data_outs: merge_branch_outputs(
+                            node,
+                            if_data_outs,
+                            &new_node.data_outs,
+                            diagnostics,
+                        ),
                         ..new_node
                     },
                     condition,
@@ -315,6 +758,100 @@ fn parse_sub_flow(
                     or_else: false_branch,
                 })
             }
+            Function::Switch {
+                data_eval,
+                cases,
+                if_data_outs,
+            } => {
+                let mut parse_branch = |handle: &str| {
+                    edge_map
+                        .get(&(node.id.to_string() + handle))
+                        .map(|targets| {
+                            parse_sub_flow(
+                                targets,
+                                node_map,
+                                edge_map,
+                                children_map,
+                                scheduled,
+                                Some(node.id.clone()),
+                                diagnostics,
+                            )
+                        })
+                        .unwrap_or_default()
+                };
+                let cases = cases
+                    .iter()
+                    .map(|case| Case {
+                        value: case.value.clone(),
+                        functions: parse_branch(&case.id),
+                    })
+                    .collect();
+                let default = parse_branch("default");
+
+                // the evaluated value is referenced through the switch's own input
+                let data_eval = data_eval.and_then(|id| {
+                    new_node
+                        .data_ins
+                        .as_ref()?
+                        .iter()
+                        .find(|d| d.id == id)
+                        .map(|d| DataInOrOut {
+                            id: d.id.clone(),
+                            name: d.name.clone(),
+                            typ: d.typ.clone(),
+                            source: Some(node.name.clone() + "/" + &d.name),
+                            properties: None,
+                            constraints: None,
+                        })
+                });
+                match data_eval {
+                    Some(data_eval) => res.push(ExportedFunction::Switch {
+                        node: Node {
+                            data_outs: merge_branch_outputs(
+                                node,
+                                if_data_outs,
+                                &new_node.data_outs,
+                                diagnostics,
+                            ),
+                            ..new_node
+                        },
+                        data_eval,
+                        cases,
+                        default,
+                    }),
+                    None => diagnostics.errors.push(ExportError::MissingSwitchData {
+                        node_id: node.id.clone(),
+                    }),
+                }
+            }
+            Function::Parallel { sections } => {
+                let parallel_body: Vec<Section> = sections
+                    .iter()
+                    .filter_map(|section| edge_map.get(&(node.id.to_string() + &section.id)))
+                    .map(|targets| Section {
+                        section: parse_sub_flow(
+                            targets,
+                            node_map,
+                            edge_map,
+                            children_map,
+                            scheduled,
+                            Some(node.id.clone()),
+                            diagnostics,
+                        ),
+                    })
+                    .filter(|section| !section.section.is_empty())
+                    .collect();
+                if parallel_body.is_empty() {
+                    diagnostics.warnings.push(ExportWarning::EmptyCompound {
+                        node_id: node.id.clone(),
+                    });
+                } else {
+                    res.push(ExportedFunction::Parallel {
+                        node: new_node,
+                        parallel_body,
+                    });
+                }
+            }
             Function::ParallelFor {
                 iterators,
                 loop_counter,
@@ -331,15 +868,16 @@ fn parse_sub_flow(
                             node_map,
                             edge_map,
                             children_map,
-                            dependency_count_map,
+                            scheduled,
                             Some(node.id.clone()),
+                            diagnostics,
                         ),
                         iterators: if loop_counter_mode { None } else { iterators },
                         loop_counter: if loop_counter_mode {
                             loop_counter.map(|lc| LoopCounter {
-                                name: None,
-                                typ: lc.typ,
-                                from: None,
+                                name: lc.name,
+                                typ: declared_type(&Some(lc.typ)).unwrap_or("number".to_string()),
+                                from: lc.from,
                                 to: lc.to.unwrap_or("".to_string()),
                                 step: lc.step.unwrap_or("".to_string()),
                             })
@@ -347,6 +885,10 @@ fn parse_sub_flow(
                             None
                         },
                     })
+                } else {
+                    diagnostics.warnings.push(ExportWarning::EmptyCompound {
+                        node_id: node.id.clone(),
+                    });
                 }
             }
             Function::SequentialWhile { condition, .. } => {
@@ -358,19 +900,48 @@ fn parse_sub_flow(
                             node_map,
                             edge_map,
                             children_map,
-                            dependency_count_map,
+                            scheduled,
                             Some(node.id.clone()),
+                            diagnostics,
                         ),
                         condition,
                     })
+                } else {
+                    diagnostics.warnings.push(ExportWarning::EmptyCompound {
+                        node_id: node.id.clone(),
+                    });
                 }
             }
             Function::SequentialFor {
                 data_loops,
                 loop_counter,
-                ..
             } => {
                 if let Some(children) = children_map.get(node.id.as_str()) {
+                    let mut resolve_source = |data: &Option<InternalDataInOrOut>| {
+                        let data = data.as_ref()?;
+                        let trail = &mut Trail::default();
+                        match get_data_input(data, node.id.clone(), node_map, edge_map, trail) {
+                            Ok(data) => data.source,
+                            Err(e) => {
+                                diagnostics.errors.push(e);
+                                None
+                            }
+                        }
+                    };
+                    let data_loops: Vec<DataLoop> = data_loops
+                        .iter()
+                        .map(|data_loop| DataLoop {
+                            name: data_loop.name.clone(),
+                            typ: produced_type(node, &data_loop.id, node_map)
+                                .unwrap_or("string".to_string()),
+                            init_source: resolve_source(&data_loop.init_source),
+                            loop_source: resolve_source(&data_loop.loop_source),
+                            value: data_loop.value.clone(),
+                            properties: None,
+                            constraints: None,
+                        })
+                        .collect();
+
                     res.push(ExportedFunction::SequentialFor {
                         node: new_node,
                         loop_body: parse_sub_flow(
@@ -378,37 +949,575 @@ fn parse_sub_flow(
                             node_map,
                             edge_map,
                             children_map,
-                            dependency_count_map,
-                            node.parent_id.clone(),
+                            scheduled,
+                            Some(node.id.clone()),
+                            diagnostics,
                         ),
-                        data_loops,
-                        loop_counter,
+                        data_loops: if data_loops.is_empty() {
+                            None
+                        } else {
+                            Some(data_loops)
+                        },
+                        loop_counter: LoopCounter {
+                            name: loop_counter.name,
+                            typ: declared_type(&Some(loop_counter.typ))
+                                .unwrap_or("number".to_string()),
+                            from: Some(loop_counter.from.unwrap_or("0".to_string())),
+                            to: loop_counter.to.unwrap_or("".to_string()),
+                            step: loop_counter.step.unwrap_or("1".to_string()),
+                        },
                     })
+                } else {
+                    diagnostics.warnings.push(ExportWarning::EmptyCompound {
+                        node_id: node.id.clone(),
+                    });
                 }
             }
-            Function::StartOrEnd { .. } => {}
-        }
-        if let Some(funcs) = edge_map.get(node.id.as_str()) {
-            res.extend(parse_sub_flow(
-                funcs,
-                node_map,
-                edge_map,
-                children_map,
-                dependency_count_map,
-                node.parent_id.clone(),
-            ))
+            Function::StartOrEnd => {}
         }
     }
 
+    // only a cycle leaves nodes behind, and cycles fail the export before it gets here
+    debug_assert!(remaining.is_empty(), "unscheduled nodes left in a scope");
+
     res
 }
 
+/// Combines the outputs of the branches of an if or switch node. Every merged output
+/// lists the branch outputs it can come from as comma separated sources, all of which
+/// must have the type of the merged output.
+fn merge_branch_outputs(
+    node: &Node,
+    if_data_outs: Option<Vec<IfDataOut>>,
+    branch_outs: &Option<Vec<DataInOrOut>>,
+    diagnostics: &mut Diagnostics,
+) -> Option<Vec<DataInOrOut>> {
+    if_data_outs.map(|list| {
+        list.iter()
+            .map(|internal_out| {
+                let data_in_out = branch_outs.clone().unwrap_or(vec![]);
+
+                let sources: Vec<&DataInOrOut> = data_in_out
+                    .iter()
+                    .filter(|dio| internal_out.sources.contains(&dio.id))
+                    .collect();
+
+                let typ = declared_type(&Some(internal_out.typ.clone()))
+                    .or_else(|| sources.first().map(|s| s.typ.clone()))
+                    .unwrap_or("string".to_string());
+                for source in sources.iter().filter(|s| !types_match(&typ, &s.typ)) {
+                    diagnostics.errors.push(ExportError::TypeMismatch {
+                        node_id: node.id.clone(),
+                        data_id: internal_out.id.clone(),
+                        expected: typ.clone(),
+                        found: source.typ.clone(),
+                    });
+                }
+
+                let merged = DataInOrOut {
+                    id: internal_out.id.clone(),
+                    name: internal_out.name.clone(),
+                    typ,
+                    source: Some(
+                        sources
+                            .iter()
+                            .map(|dio| dio.source.clone().unwrap_or("".to_string()))
+                            .collect::<Vec<String>>()
+                            .join(","),
+                    ),
+                    properties: None,
+                    constraints: None,
+                };
+                if let Some(explanations) = diagnostics.explanations.as_mut() {
+                    explanations.push(Explanation {
+                        node_id: node.id.clone(),
+                        node: node.name.clone(),
+                        data_id: merged.id.clone(),
+                        direction: Direction::Out,
+                        name: merged.name.clone(),
+                        source: merged.source.clone(),
+                        rule: SourceRule::MergedBranches,
+                        visited: vec![],
+                    });
+                }
+                merged
+            })
+            .collect()
+    })
+}
+
+fn negate_combinator(combinator: &str) -> String {
+    match combinator {
+        "and" => "or".to_string(),
+        "or" => "and".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Lowers a condition group to the flat AFCL list, in which all conditions are combined
+/// the same way. Negations are pushed down to the conditions with De Morgan's laws, nested
+/// groups are merged into their parent if both combine the same way. Returns the combinator
+/// of the list, `None` if the group mixes `and` and `or` and cannot be written as a list.
+fn lower_condition_group(
+    mut group: ConditionGroup,
+    negated: bool,
+    lowered: &mut Vec<EditorCondition>,
+) -> Option<String> {
+    let negated = negated != group.negation.unwrap_or(false);
+    // a group around a single group only adds parentheses
+    if group.conditions.len() == 1 && matches!(group.conditions[0], ConditionTerm::Group(_)) {
+        if let Some(ConditionTerm::Group(inner)) = group.conditions.pop() {
+            return lower_condition_group(inner, negated, lowered);
+        }
+    }
+    let combinator = if negated {
+        negate_combinator(&group.combined_with)
+    } else {
+        group.combined_with
+    };
+    for term in group.conditions {
+        match term {
+            ConditionTerm::Condition(condition) => lowered.push(EditorCondition {
+                negation: Some(condition.negation.unwrap_or(false) != negated),
+                combined_with: Some(combinator.clone()),
+                ..condition
+            }),
+            ConditionTerm::Group(group) => {
+                let mut nested = vec![];
+                let nested_combinator = lower_condition_group(group, negated, &mut nested)?;
+                if nested.len() > 1 && nested_combinator != combinator {
+                    return None;
+                }
+                lowered.extend(nested.into_iter().map(|condition| EditorCondition {
+                    combined_with: Some(combinator.clone()),
+                    ..condition
+                }));
+            }
+        }
+    }
+    Some(combinator)
+}
+
+/// Parses the conditions of an if or while node. Conditions with an unknown operator or an
+/// operator that cannot compare their type are reported and left out, as are all conditions
+/// if they mix `and` with `or`.
+fn export_conditions(
+    node_id: &str,
+    conditions: Option<Conditions>,
+    diagnostics: &mut Diagnostics,
+) -> Vec<Condition> {
+    let conditions = match conditions {
+        None => vec![],
+        Some(Conditions::List(conditions)) => {
+            // the editor stores each join on the condition before it, so the combinator of
+            // the last condition joins nothing and the others must agree
+            let joins = &conditions[..conditions.len().saturating_sub(1)];
+            let mut combinators = joins
+                .iter()
+                .map(|c| c.combined_with.as_deref().unwrap_or("or"))
+                .filter(|combinator| CombineWith::parse(combinator).is_some());
+            if let Some(first) = combinators.next() {
+                if combinators.any(|combinator| combinator != first) {
+                    diagnostics
+                        .errors
+                        .push(ExportError::InexpressibleCondition {
+                            node_id: node_id.to_string(),
+                        });
+                    return vec![];
+                }
+            }
+            conditions
+        }
+        Some(Conditions::Group(group)) => {
+            let mut lowered = vec![];
+            if lower_condition_group(group, false, &mut lowered).is_none() {
+                diagnostics
+                    .errors
+                    .push(ExportError::InexpressibleCondition {
+                        node_id: node_id.to_string(),
+                    });
+                return vec![];
+            }
+            lowered
+        }
+    };
+    conditions
+        .into_iter()
+        .filter_map(|c| {
+            let typ = declared_type(&c.typ).unwrap_or("string".to_string());
+            let combinator = c.combined_with.unwrap_or("or".to_string());
+            let Some(operator) = ConditionOperator::parse(&c.operator) else {
+                diagnostics
+                    .errors
+                    .push(ExportError::UnknownConditionOperator {
+                        node_id: node_id.to_string(),
+                        operator: c.operator,
+                    });
+                return None;
+            };
+            let Some(combined_with) = CombineWith::parse(&combinator) else {
+                diagnostics
+                    .errors
+                    .push(ExportError::UnknownConditionCombinator {
+                        node_id: node_id.to_string(),
+                        combinator,
+                    });
+                return None;
+            };
+            if !operator.applies_to(&typ) {
+                diagnostics.errors.push(ExportError::IncompatibleOperator {
+                    node_id: node_id.to_string(),
+                    operator: operator.as_str().to_string(),
+                    typ,
+                });
+                return None;
+            }
+            let mut references = vec![];
+            let mut guessed_references = vec![];
+            let mut operand = |data: String, kind: Option<OperandKind>| match kind {
+                Some(OperandKind::Reference) => {
+                    references.push(data.clone());
+                    Value::String(data)
+                }
+                None if data.contains('/') => {
+                    guessed_references.push(data.clone());
+                    Value::String(data)
+                }
+                _ => condition_value(data, &typ),
+            };
+            let data1 = operand(c.data1, c.data1_kind);
+            let data2 = operand(c.data2, c.data2_kind);
+            Some(Condition {
+                data1,
+                data2,
+                references,
+                guessed_references,
+                typ,
+                operator,
+                negation: c.negation.unwrap_or(false),
+                combined_with,
+            })
+        })
+        .collect()
+}
+
+/// Literals of number and boolean conditions are written as yaml numbers and booleans.
+fn condition_value(data: String, typ: &str) -> Value {
+    let literal = data.trim();
+    match typ {
+        "number" => literal
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| literal.parse::<f64>().map(Value::from))
+            .unwrap_or(Value::String(data)),
+        "boolean" if literal == "true" || literal == "false" => Value::Bool(literal == "true"),
+        _ => Value::String(data),
+    }
+}
+
+/// Data a condition of `node` can read, as `(node name, data name, type)`: the inputs of
+/// the node and of the compounds around it, the workflow inputs and, for while nodes, the
+/// loop counter and the outputs of the loop body.
+fn condition_scope(
+    node: &Node,
+    workflow_name: &str,
+    node_map: &HashMap<String, Node>,
+    edge_map: &HashMap<String, Vec<&Node>>,
+) -> Vec<(String, String, String)> {
+    let mut scope = vec![];
+    let mut inputs_of = |owner: &Node, name: &str, data: &Option<Vec<InternalDataInOrOut>>| {
+        for d in data.iter().flatten() {
+            let trail = &mut Trail::default();
+            if let Ok(d) = get_data_input(d, owner.id.clone(), node_map, edge_map, trail) {
+                scope.push((name.to_string(), d.name, d.typ));
+            }
+        }
+    };
+    if let Some(start) = node_map.get("0") {
+        inputs_of(start, workflow_name, &start.internal_data_outs);
+    }
+    let mut enclosing = Some(node);
+    while let Some(owner) = enclosing {
+        inputs_of(owner, &owner.name, &owner.internal_data_ins);
+        enclosing = get_parent(owner, node_map).ok().flatten();
+    }
+
+    if let Function::SequentialWhile { .. } = node.function {
+        scope.push((
+            node.name.clone(),
+            "counter".to_string(),
+            "number".to_string(),
+        ));
+        let mut body: Vec<&Node> = node_map
+            .values()
+            .filter(|n| {
+                n.id != node.id && scope_ancestor(n, &Some(node.id.clone()), node_map).is_some()
+            })
+            .collect();
+        body.sort_by(|a, b| editor_order(a, b));
+        for child in body {
+            for d in child.internal_data_outs.iter().flatten() {
+                scope.push((
+                    child.name.clone(),
+                    d.rename.clone().or(d.name.clone()).unwrap_or_default(),
+                    produced_type(child, &d.id, node_map).unwrap_or("object".to_string()),
+                ));
+            }
+        }
+    }
+    scope
+}
+
+/// Resolves the data references of all if and while conditions, so a misspelled reference
+/// fails the export instead of the workflow run.
+fn check_condition_data(
+    workflow_name: &str,
+    node_map: &HashMap<String, Node>,
+    edge_map: &HashMap<String, Vec<&Node>>,
+    diagnostics: &mut Diagnostics,
+) {
+    let mut nodes: Vec<&Node> = node_map.values().collect();
+    nodes.sort_by(|a, b| editor_order(a, b));
+    for node in nodes {
+        let (Function::IfThenElse { condition, .. } | Function::SequentialWhile { condition }) =
+            &node.function
+        else {
+            continue;
+        };
+        if condition
+            .iter()
+            .all(|c| c.references.is_empty() && c.guessed_references.is_empty())
+        {
+            continue;
+        }
+        let scope = condition_scope(node, workflow_name, node_map, edge_map);
+        for c in condition {
+            let references = c.references.iter().map(|r| (r, false));
+            let guessed = c.guessed_references.iter().map(|r| (r, true));
+            for (reference, guessed) in references.chain(guessed) {
+                let found = reference.split_once('/').and_then(|(owner, data)| {
+                    scope
+                        .iter()
+                        .find(|(name, data_name, _)| name == owner && data_name == data)
+                });
+                match found {
+                    Some((_, _, typ)) if !condition_compares(&c.typ, typ) => {
+                        diagnostics.errors.push(ExportError::ConditionTypeMismatch {
+                            node_id: node.id.clone(),
+                            reference: reference.clone(),
+                            expected: c.typ.clone(),
+                            found: typ.clone(),
+                        })
+                    }
+                    Some(_) => {}
+                    None if guessed => {
+                        diagnostics
+                            .warnings
+                            .push(ExportWarning::LiteralConditionData {
+                                node_id: node.id.clone(),
+                                data: reference.clone(),
+                            })
+                    }
+                    None => diagnostics.errors.push(ExportError::UnknownConditionData {
+                        node_id: node.id.clone(),
+                        reference: reference.clone(),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+/// Orders nodes top to bottom, then left to right, then by name, so the same graph
+/// exports the same yaml no matter in which order its nodes and edges were saved.
+fn editor_order(a: &Node, b: &Node) -> Ordering {
+    a.position
+        .y
+        .total_cmp(&b.position.y)
+        .then(a.position.x.total_cmp(&b.position.x))
+        .then_with(|| a.name.cmp(&b.name))
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+/// Exports the data in- or outputs of a node, explaining each if `diagnostics` collect
+/// explanations.
+fn resolve_data(
+    data: &Option<Vec<InternalDataInOrOut>>,
+    node_id: &str,
+    direction: Direction,
+    node_map: &HashMap<String, Node>,
+    edge_map: &HashMap<String, Vec<&Node>>,
+    diagnostics: &mut Diagnostics,
+) -> Option<Vec<DataInOrOut>> {
+    data.as_ref().map(|d| {
+        d.iter()
+            .filter_map(|d| {
+                let mut trail = Trail::default();
+                match get_data_input(d, node_id.to_string(), node_map, edge_map, &mut trail) {
+                    Ok(data) => {
+                        if let (Some(explanations), Some(rule)) =
+                            (diagnostics.explanations.as_mut(), trail.rule)
+                        {
+                            explanations.push(Explanation {
+                                node_id: node_id.to_string(),
+                                node: node_map
+                                    .get(node_id)
+                                    .map(|n| n.name.clone())
+                                    .unwrap_or_default(),
+                                data_id: d.id.clone(),
+                                direction,
+                                name: data.name.clone(),
+                                source: data.source.clone(),
+                                rule,
+                                visited: trail.visited,
+                            });
+                        }
+                        // only data of another node can differ from the declared type
+                        let referenced = d.source.as_ref().is_some_and(|s| s != node_id);
+                        match declared_type(&d.typ) {
+                            Some(expected) if referenced && !types_match(&expected, &data.typ) => {
+                                diagnostics.errors.push(ExportError::TypeMismatch {
+                                    node_id: node_id.to_string(),
+                                    data_id: d.id.clone(),
+                                    expected,
+                                    found: data.typ.clone(),
+                                })
+                            }
+                            _ => {}
+                        }
+                        Some(data)
+                    }
+                    Err(e) => {
+                        diagnostics.errors.push(e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    })
+}
+
+/// Whether a condition of type `expected` can compare data of type `found`. Number
+/// conditions compare collections by their length.
+fn condition_compares(expected: &str, found: &str) -> bool {
+    types_match(expected, found) || (expected == "number" && is_collection(found))
+}
+
+/// Collections are declared as `array` by the editor and as `collection` in some workflows.
+fn is_collection(typ: &str) -> bool {
+    typ == "array" || typ == "collection"
+}
+
+/// Whether data of type `found` can be used where `expected` is declared.
+fn types_match(expected: &str, found: &str) -> bool {
+    expected == found
+        || expected == "object"
+        || found == "object"
+        || (is_collection(expected) && is_collection(found))
+}
+
+fn declared_type(typ: &Option<String>) -> Option<String> {
+    typ.clone().filter(|t| !t.trim().is_empty())
+}
+
+/// Type of a constant written in the editor.
+fn literal_type(value: &Option<String>) -> String {
+    match value.as_deref().map(str::trim) {
+        Some("true" | "false") => "boolean".to_string(),
+        Some(v) if v.parse::<f64>().is_ok() => "number".to_string(),
+        _ => "string".to_string(),
+    }
+}
+
+/// Type of the data `data_id` produced by `node`. Compound outputs and inputs are followed
+/// to the node producing the data until a declared type is found, data leaving a parallel
+/// for is a collection. `None` if no node on the way declares a type.
+fn produced_type(node: &Node, data_id: &str, node_map: &HashMap<String, Node>) -> Option<String> {
+    produced_type_within(node, data_id, node_map, node_map.len())
+}
+
+fn produced_type_within(
+    node: &Node,
+    data_id: &str,
+    node_map: &HashMap<String, Node>,
+    depth: usize,
+) -> Option<String> {
+    let depth = depth.checked_sub(1)?;
+    match &node.function {
+        Function::IfThenElse { if_data_outs, .. } | Function::Switch { if_data_outs, .. } => {
+            if let Some(out) = if_data_outs.iter().flatten().find(|d| d.id == data_id) {
+                return declared_type(&Some(out.typ.clone())).or_else(|| {
+                    out.sources
+                        .iter()
+                        .find_map(|s| produced_type_within(node, s, node_map, depth))
+                });
+            }
+        }
+        Function::SequentialFor { data_loops, .. } => {
+            if let Some(data_loop) = data_loops.iter().find(|l| l.id == data_id) {
+                return declared_type(&data_loop.typ).or_else(|| {
+                    let init = data_loop.init_source.as_ref()?;
+                    let source = node_map.get(init.source.as_ref()?)?;
+                    produced_type_within(source, &init.id, node_map, depth)
+                });
+            }
+        }
+        _ => {}
+    }
+
+    let data = node
+        .internal_data_outs
+        .iter()
+        .flatten()
+        .chain(node.internal_data_ins.iter().flatten())
+        .find(|d| d.id == data_id)?;
+    let inferred = declared_type(&data.typ).or_else(|| match data.source.as_ref() {
+        Some(source) if *source == node.id => Some(literal_type(&data.value)),
+        Some(source) => produced_type_within(node_map.get(source)?, data_id, node_map, depth),
+        None => None,
+    });
+    let leaves_parallel_for = matches!(node.function, Function::ParallelFor { .. })
+        && node
+            .internal_data_outs
+            .iter()
+            .flatten()
+            .any(|d| d.id == data_id);
+    if leaves_parallel_for {
+        return Some(
+            inferred
+                .filter(|t| is_collection(t))
+                .unwrap_or("array".to_string()),
+        );
+    }
+    inferred
+}
+
+fn get_parent<'a>(
+    node: &Node,
+    node_map: &'a HashMap<String, Node>,
+) -> Result<Option<&'a Node>, ExportError> {
+    node.parent_id
+        .as_ref()
+        .map(|parent_id| {
+            node_map
+                .get(parent_id)
+                .ok_or_else(|| ExportError::ParentNotFound {
+                    node_id: node.id.clone(),
+                    parent_id: parent_id.clone(),
+                })
+        })
+        .transpose()
+}
+
+/// Exports a data in- or output of the node `node_id`, finding its source. The rule
+/// deciding the source and the nodes looked at are recorded in `trail`.
 fn get_data_input(
     data: &InternalDataInOrOut,
     node_id: String,
     node_map: &HashMap<String, Node>,
     edge_map: &HashMap<String, Vec<&Node>>,
-) -> DataInOrOut {
+    trail: &mut Trail,
+) -> Result<DataInOrOut, ExportError> {
     let actual_name = data
         .rename
         .clone()
@@ -416,34 +1525,52 @@ fn get_data_input(
 
     if let Some(source) = &data.source {
         if *source == node_id {
-            DataInOrOut {
+            trail.rule = Some(SourceRule::Constant);
+            Ok(DataInOrOut {
                 id: data.id.clone(),
                 name: actual_name,
-                typ: data.typ.clone().unwrap_or("".into()),
+                typ: declared_type(&data.typ).unwrap_or_else(|| literal_type(&data.value)),
                 source: data.value.clone(),
                 properties: data.properties.clone(),
                 constraints: data.constraints.clone(),
-            }
+            })
         } else {
-            let source_node = node_map.get(source).expect("source node not found");
-            let current_node = node_map.get(&node_id).expect("current node not found");
-
-            if let Some(Node {
-                id,
-                name,
-                internal_data_ins,
-                function:
-                    Function::ParallelFor {
-                        iterators,
-                        internal_iterators,
-                        ..
-                    },
-                ..
-            }) = current_node
-                .parent_id
-                .clone()
-                .map(|c| node_map.get(&c).expect("parent not found"))
+            let source_node =
+                node_map
+                    .get(source)
+                    .ok_or_else(|| ExportError::SourceNodeNotFound {
+                        node_id: node_id.clone(),
+                        data_id: data.id.clone(),
+                        source: source.clone(),
+                    })?;
+            trail.visit(source_node, Role::Source);
+            let current_node = node_map
+                .get(&node_id)
+                .ok_or_else(|| ExportError::NodeNotFound {
+                    node_id: node_id.clone(),
+                })?;
+            let data_out_not_found = || ExportError::DataOutNotFound {
+                node_id: node_id.clone(),
+                data_id: data.id.clone(),
+                source: source.clone(),
+            };
+
+            if let Some(
+                parent @ Node {
+                    id,
+                    name,
+                    internal_data_ins,
+                    function:
+                        Function::ParallelFor {
+                            iterators,
+                            internal_iterators,
+                            ..
+                        },
+                    ..
+                },
+            ) = get_parent(current_node, node_map)?
             {
+                trail.visit(parent, Role::Parent);
                 if edge_map
                     .get(id)
                     .unwrap_or(&vec![])
@@ -459,22 +1586,31 @@ fn get_data_input(
                             .clone()
                             .unwrap_or(parent_input.name.clone().unwrap_or("".to_string()));
 
-                        let data_in_type = if iterators
-                            .clone()
-                            .unwrap_or(vec![])
-                            .contains(&parent_in_name)
+                        // children get one element of every iterated or block distributed
+                        // collection
+                        let distributed = parent_input
+                            .constraints
+                            .iter()
+                            .flatten()
+                            .any(|c| c.name == "distribution" && c.value.starts_with("BLOCK"));
+                        let data_in_type = if distributed
+                            || iterators
+                                .clone()
+                                .unwrap_or(vec![])
+                                .contains(&parent_in_name)
                         {
-                            let iterator = internal_iterators
+                            internal_iterators
                                 .iter()
-                                .find(|i| i.name == Some(parent_in_name.clone()));
-                            iterator
-                                .map(|i| i.elemnt_type.clone().unwrap_or("string".to_string()))
-                                .unwrap_or("string".to_string())
+                                .find(|i| i.name == Some(parent_in_name.clone()))
+                                .and_then(|i| declared_type(&i.elemnt_type))
                         } else {
-                            "string".to_string()
-                        };
+                            produced_type(source_node, &data.id, node_map)
+                        }
+                        .or_else(|| declared_type(&data.typ))
+                        .unwrap_or("string".to_string());
 
-                        return DataInOrOut {
+                        trail.rule = Some(SourceRule::ParallelForInput);
+                        return Ok(DataInOrOut {
                             id: data.id.clone(),
                             name: if data.rename.is_some() {
                                 actual_name
@@ -491,38 +1627,77 @@ fn get_data_input(
                             ),
                             properties: data.properties.clone(),
                             constraints: data.constraints.clone(),
-                        };
+                        });
                     }
                 }
             }
 
-            if let Function::IfThenElse { if_data_outs, .. } = &source_node.function {
+            if let Function::SequentialFor { data_loops, .. } = &source_node.function {
+                if let Some(data_loop) = data_loops.iter().find(|l| l.id == data.id) {
+                    trail.rule = Some(SourceRule::DataLoop);
+                    return Ok(DataInOrOut {
+                        id: data.id.clone(),
+                        name: data.rename.clone().unwrap_or(data_loop.name.clone()),
+                        typ: produced_type(source_node, &data.id, node_map)
+                            .or_else(|| declared_type(&data.typ))
+                            .unwrap_or("string".to_string()),
+                        source: Some(source_node.name.clone() + "/" + &data_loop.name),
+                        properties: data.properties.clone(),
+                        constraints: data.constraints.clone(),
+                    });
+                }
+            }
+
+            if let Function::IfThenElse { if_data_outs, .. }
+            | Function::Switch { if_data_outs, .. } = &source_node.function
+            {
+                // branches may read the inputs of the node they belong to
+                let input = source_node
+                    .internal_data_ins
+                    .iter()
+                    .flatten()
+                    .find(|d| d.id == data.id);
+                if let Some(input) = input {
+                    let input_name = input
+                        .rename
+                        .clone()
+                        .unwrap_or(input.name.clone().unwrap_or_default());
+                    trail.visit(source_node, Role::Enclosing);
+                    trail.rule = Some(SourceRule::EnclosingInput);
+                    return Ok(DataInOrOut {
+                        id: data.id.clone(),
+                        name: data.rename.clone().unwrap_or(input_name.clone()),
+                        typ: produced_type(source_node, &data.id, node_map)
+                            .or_else(|| declared_type(&data.typ))
+                            .unwrap_or("string".to_string()),
+                        source: Some(source_node.name.clone() + "/" + &input_name),
+                        properties: data.properties.clone(),
+                        constraints: data.constraints.clone(),
+                    });
+                }
                 let data_out = if_data_outs
                     .as_ref()
-                    .map(|d| {
-                        d.iter()
-                            .find(|d| *d.id == data.id)
-                            .expect("output data not found")
-                    })
-                    .expect("no data outs found");
-                DataInOrOut {
+                    .and_then(|d| d.iter().find(|d| *d.id == data.id))
+                    .ok_or_else(data_out_not_found)?;
+                trail.rule = Some(SourceRule::BranchOutput);
+                Ok(DataInOrOut {
                     id: data.id.clone(),
                     name: actual_name,
-                    typ: data_out.typ.clone(),
+                    typ: produced_type(source_node, &data.id, node_map)
+                        .or_else(|| declared_type(&data.typ))
+                        .unwrap_or("string".to_string()),
                     source: Some(source_node.name.clone() + "/" + &data_out.name.clone()),
                     properties: data.properties.clone(),
                     constraints: data.constraints.clone(),
-                }
+                })
             } else {
-                let mut source_parent = source_node.clone();
+                let mut source_parent = source_node;
                 while source_parent.parent_id.is_some()
-                    && source_parent.clone().parent_id.unwrap() != node_id
+                    && source_parent.parent_id.as_ref() != Some(&node_id)
                     && source_parent.parent_id != current_node.parent_id
                 {
-                    source_parent = node_map
-                        .get(&source_parent.parent_id.unwrap())
-                        .expect("Parent not found")
-                        .clone();
+                    source_parent = get_parent(source_parent, node_map)?.unwrap();
+                    trail.visit(source_parent, Role::SourceParent);
                 }
 
                 let parent_data_out = source_parent
@@ -534,19 +1709,15 @@ fn get_data_input(
                 let data_out = source_node
                     .internal_data_outs
                     .as_ref()
-                    .map(|d| {
-                        d.iter()
-                            .find(|d| *d.id == data.id)
-                            .or_else(|| {
-                                source_node
-                                    .internal_data_ins
-                                    .as_ref()
-                                    .map(|i| i.iter().find(|d| *d.id == data.id))
-                                    .unwrap_or(None)
-                            })
-                            .expect("output data not found")
+                    .and_then(|d| {
+                        d.iter().find(|d| *d.id == data.id).or_else(|| {
+                            source_node
+                                .internal_data_ins
+                                .as_ref()
+                                .and_then(|i| i.iter().find(|d| *d.id == data.id))
+                        })
                     })
-                    .expect("no data outs found");
+                    .ok_or_else(data_out_not_found)?;
 
                 let mut data_out_name = parent_data_out
                     .map(|p| p.rename.clone())
@@ -564,38 +1735,28 @@ fn get_data_input(
                     data_out_name.clone()
                 };
 
-                let data_type = if let Function::ParallelFor { .. } = source_parent.function {
-                    "array".to_string()
+                // outputs of a compound are typed by the compound, a parallel for collects them
+                let producer = if source_parent.parent_id.as_ref() == Some(&node_id) {
+                    current_node
                 } else {
-                    if let Function::ParallelFor {
-                        iterators,
-                        internal_iterators,
-                        ..
-                    } = &current_node.function
-                    {
-                        if iterators.clone().unwrap_or(vec![]).contains(&correct_name) {
-                            let iterator = internal_iterators
-                                .iter()
-                                .find(|i| i.name == Some(correct_name.clone()))
-                                .unwrap();
-                            iterator.elemnt_type.clone().unwrap_or("string".to_string())
-                        } else {
-                            "array".to_string()
-                        }
-                    } else {
-                        data_out.typ.clone().unwrap_or("string".to_string())
-                    }
+                    source_parent
                 };
+                let data_type = produced_type(producer, &data.id, node_map)
+                    .or_else(|| produced_type(source_node, &data.id, node_map))
+                    .or_else(|| declared_type(&data.typ))
+                    .unwrap_or("string".to_string());
 
-                let correct_source_name = if let Some(Node {
-                    name,
-                    internal_data_ins,
-                    function: Function::SequentialWhile { .. },
-                    ..
-                }) = current_node
-                    .parent_id
-                    .clone()
-                    .map(|c| node_map.get(&c).expect("parent not found"))
+                trail.rule = Some(SourceRule::Output);
+                let correct_source_name = if let Some(
+                    parent @ Node {
+                        internal_data_ins,
+                        function:
+                            Function::SequentialWhile { .. }
+                            | Function::SequentialFor { .. }
+                            | Function::SubFC,
+                        ..
+                    },
+                ) = get_parent(current_node, node_map)?
                 {
                     if let Some(Some(parent_input)) = internal_data_ins
                         .as_ref()
@@ -607,7 +1768,14 @@ fn get_data_input(
                                 .clone()
                                 .unwrap_or(data_out_name.to_string()),
                         );
-                        name.clone()
+                        trail.visit(parent, Role::Enclosing);
+                        trail.rule = Some(SourceRule::EnclosingInput);
+                        // the body of a sub-FC refers to its inputs by the sub-FC name
+                        if let Function::SubFC = parent.function {
+                            parent.typ.clone()
+                        } else {
+                            parent.name.clone()
+                        }
                     } else {
                         source_parent.name.clone()
                     }
@@ -615,41 +1783,98 @@ fn get_data_input(
                     source_parent.name.clone()
                 };
 
-                DataInOrOut {
+                Ok(DataInOrOut {
                     id: data.id.clone(),
                     name: correct_name,
                     typ: data_type,
                     source: Some(correct_source_name + "/" + &data_out_name),
                     properties: data.properties.clone(),
                     constraints: data.constraints.clone(),
-                }
+                })
             }
         }
     } else {
-        DataInOrOut {
+        trail.rule = Some(SourceRule::Unconnected);
+        Ok(DataInOrOut {
             id: data.id.clone(),
             name: actual_name,
-            typ: data.typ.clone().unwrap_or("".into()),
+            typ: declared_type(&data.typ).unwrap_or("string".to_string()),
             source: data.start_source.clone(),
             properties: data.properties.clone(),
             constraints: data.constraints.clone(),
-        }
+        })
     }
 }
 
-pub fn export_from_flow(workflow: Workflow) -> ApolloYaml {
+/// Converts the editor graph into AFCL. Problems are collected in `diagnostics`;
+/// `None` is returned if the workflow has no start or end node, a node whose parent is
+/// missing or a cycle.
+pub fn export_from_flow(workflow: Workflow, diagnostics: &mut Diagnostics) -> Option<ApolloYaml> {
     let mut node_map: HashMap<String, Node> = HashMap::new();
     let mut edge_map: HashMap<String, Vec<&Node>> = HashMap::new();
     let mut children_map: HashMap<String, Vec<Node>> = HashMap::new();
 
-    let mut dependency_count_map: HashMap<String, usize> = HashMap::new();
+    let mut scheduled: HashSet<String> = HashSet::new();
+    let mut sub_fc_ids: Vec<String> = vec![];
 
     for node in workflow.data.nodes {
+        let function = match node.typ.as_str() {
+            "function" => Function::AtomicFunction,
+            "subFC" => {
+                sub_fc_ids.push(node.id.clone());
+                Function::SubFC
+            }
+            "start" => Function::StartOrEnd,
+            "end" => Function::StartOrEnd,
+            "if" => Function::IfThenElse {
+                condition: export_conditions(&node.id, node.data.conditions, diagnostics),
+                if_data_outs: node.data.if_data_outs,
+            },
+            "switch" => Function::Switch {
+                data_eval: node.data.switch_data,
+                cases: node.data.cases.unwrap_or(vec![]),
+                if_data_outs: node.data.if_data_outs,
+            },
+            "for" => Function::SequentialFor {
+                data_loops: node.data.data_loops.unwrap_or(vec![]),
+                loop_counter: node.data.loop_counter.unwrap_or(InternalLoopCounter {
+                    enabled: true,
+                    name: None,
+                    from: None,
+                    to: None,
+                    typ: "number".to_string(),
+                    step: None,
+                }),
+            },
+            "parallelSections" => Function::Parallel {
+                sections: node.data.sections.unwrap_or(vec![]),
+            },
+            "parallel" => Function::ParallelFor {
+                iterators: node.data.iterators.clone().map(|o| {
+                    o.iter()
+                        .map(|i| i.name.clone().unwrap_or("".to_string()))
+                        .collect()
+                }),
+                internal_iterators: node.data.iterators.unwrap_or(vec![]),
+                loop_counter: node.data.loop_counter,
+            },
+            "while" => Function::SequentialWhile {
+                condition: export_conditions(&node.id, node.data.conditions, diagnostics),
+            },
+            _ => {
+                diagnostics.errors.push(ExportError::UnknownNodeType {
+                    node_id: node.id.clone(),
+                    node_type: node.typ.clone(),
+                });
+                continue;
+            }
+        };
         node_map.insert(
             node.id.to_string(),
             Node {
                 id: node.id.to_string(),
                 parent_id: node.parent_node.clone(),
+                position: node.position.clone(),
                 name: node.data.name.clone(),
                 typ: node.data.function_type.clone(),
                 data_ins: None,
@@ -658,64 +1883,12 @@ pub fn export_from_flow(workflow: Workflow) -> ApolloYaml {
                 internal_data_outs: node.data.data_outs.clone(),
                 properties: node.data.properties.clone(),
                 constraints: node.data.constraints.clone(),
-                function: match node.typ.as_str() {
-                    "function" => Function::AtomicFunction,
-                    "start" => Function::StartOrEnd,
-                    "end" => Function::StartOrEnd,
-                    "if" => Function::IfThenElse {
-                        condition: node
-                            .data
-                            .conditions
-                            .unwrap_or(vec![])
-                            .iter()
-                            .map(|c| Condition {
-                                data1: c.data1.clone(),
-                                data2: c.data2.clone(),
-                                typ: c.typ.clone().unwrap_or("string".to_string()),
-                                operator: c.operator.clone(),
-                                negation: c.negation.clone().unwrap_or(false).to_string(),
-                                combined_with: c.combined_with.clone().unwrap_or("or".to_string()),
-                            })
-                            .collect(),
-                        if_data_outs: node.data.if_data_outs,
-                    },
-                    "parallel" => Function::ParallelFor {
-                        iterators: node.data.iterators.clone().map(|o| {
-                            o.iter()
-                                .map(|i| i.name.clone().unwrap_or("".to_string()))
-                                .collect()
-                        }),
-                        internal_iterators: node.data.iterators.unwrap_or(vec![]),
-                        loop_counter: node.data.loop_counter,
-                    },
-                    "while" => Function::SequentialWhile {
-                        condition: node
-                            .data
-                            .conditions
-                            .unwrap_or(vec![])
-                            .iter()
-                            .map(|c| Condition {
-                                data1: c.data1.clone(),
-                                data2: c.data2.clone(),
-                                typ: c.typ.clone().unwrap_or("string".to_string()),
-                                operator: c.operator.clone(),
-                                negation: c.negation.clone().unwrap_or(false).to_string(),
-                                combined_with: c.combined_with.clone().unwrap_or("or".to_string()),
-                            })
-                            .collect(),
-                    },
-                    _ => panic!(
-                        "unknown or unimplemented node type: {}",
-                        node.typ.to_string()
-                    ),
-                },
+                function,
             },
         );
 
-        if node.parent_node.is_some() {
-            let entry = children_map
-                .entry(node.parent_node.unwrap().to_string())
-                .or_insert(Vec::new());
+        if let Some(parent_node) = node.parent_node {
+            let entry = children_map.entry(parent_node).or_default();
             entry.push(node_map.get(&node.id).unwrap().clone());
         }
     }
@@ -723,55 +1896,168 @@ pub fn export_from_flow(workflow: Workflow) -> ApolloYaml {
     for edge in workflow.data.edges {
         let mut edge_name = edge.source;
 
-        if let Some(..) = node_map.get(&edge_name) {
-            if edge.source_handle.starts_with("i") {
-                // continue;
-            }
+        if node_map.contains_key(&edge.target) && edge.target_handle.starts_with('o') {
+            continue;
         }
 
-        if let Some(..) = node_map.get(&edge.target) {
-            if edge.target_handle.starts_with("o") {
-                continue;
+        // edges starting a branch of a compound are keyed by node and handle
+        let handle = &edge.source_handle;
+        let is_branch = match node_map.get(&edge_name).map(|n| &n.function) {
+            Some(Function::Switch { cases, .. }) => {
+                handle == "default" || cases.iter().any(|c| c.id == *handle)
             }
+            Some(Function::Parallel { sections }) => sections.iter().any(|s| s.id == *handle),
+            _ => handle == "true" || handle == "false",
+        };
+        if is_branch {
+            edge_name.push_str(handle);
         }
-
-        if edge.source_handle == "true" || edge.source_handle == "false" {
-            edge_name.push_str(&edge.source_handle);
-        }
-        let entry = edge_map.entry(edge_name).or_insert(Vec::new());
+        let entry = edge_map.entry(edge_name).or_default();
         let edge_target = edge.target.clone();
 
         if !entry.iter().any(|f| f.id == edge_target) {
             if let Some(func) = node_map.get(&edge.target) {
                 entry.push(func);
-                *dependency_count_map.entry(edge_target).or_default() += 0;
             }
         }
     }
 
-    let start_node = node_map.get("0").expect("Start node not found!");
-    let end_node = node_map.get("end").expect("End node not found!");
+    for targets in edge_map.values_mut() {
+        targets.sort_by(|a, b| editor_order(a, b));
+    }
+    for children in children_map.values_mut() {
+        children.sort_by(editor_order);
+    }
+    sub_fc_ids.sort_by(|a, b| editor_order(&node_map[a], &node_map[b]));
 
-    return ApolloYaml {
-        name: workflow.name,
-        data_ins: start_node.internal_data_outs.as_ref().map(|d| {
-            d.iter()
-                .map(|d| get_data_input(d, start_node.id.clone(), &node_map, &edge_map))
-                .collect()
-        }),
-        workflow_body: parse_sub_flow(
-            edge_map.get("0").unwrap(),
+    // children of a missing compound would silently be left out
+    let mut nodes: Vec<&Node> = node_map.values().collect();
+    nodes.sort_by(|a, b| editor_order(a, b));
+    let orphans: Vec<ExportError> = nodes
+        .into_iter()
+        .filter_map(|node| get_parent(node, &node_map).err())
+        .collect();
+    if !orphans.is_empty() {
+        diagnostics.errors.extend(orphans);
+        return None;
+    }
+
+    let cycles = find_cycles(&node_map, &edge_map, &children_map);
+    if !cycles.is_empty() {
+        diagnostics
+            .errors
+            .extend(cycles.into_iter().map(|cycle| ExportError::Cycle {
+                node_id: cycle[0].id.clone(),
+                names: cycle.iter().map(|n| n.name.clone()).collect(),
+            }));
+        return None;
+    }
+    check_condition_data(&workflow.name, &node_map, &edge_map, diagnostics);
+
+    let Some(start_node) = node_map.get("0") else {
+        diagnostics.errors.push(ExportError::MissingStartNode);
+        return None;
+    };
+    let Some(end_node) = node_map.get("end") else {
+        diagnostics.errors.push(ExportError::MissingEndNode);
+        return None;
+    };
+    let start_targets = edge_map.get("0").cloned().unwrap_or_else(|| {
+        diagnostics.warnings.push(ExportWarning::StartNotConnected);
+        vec![]
+    });
+
+    let workflow_body = parse_sub_flow(
+        &start_targets,
+        &node_map,
+        &edge_map,
+        &children_map,
+        &mut scheduled,
+        None,
+        diagnostics,
+    );
+
+    let mut sub_fcs: Vec<SubFC> = vec![];
+    for node in sub_fc_ids.iter().filter_map(|id| node_map.get(id)) {
+        if sub_fcs.iter().any(|s| s.name == node.typ) {
+            diagnostics.warnings.push(ExportWarning::DuplicateSubFC {
+                node_id: node.id.clone(),
+                name: node.typ.clone(),
+            });
+            continue;
+        }
+        let Some(children) = children_map.get(&node.id) else {
+            diagnostics.warnings.push(ExportWarning::EmptyCompound {
+                node_id: node.id.clone(),
+            });
+            continue;
+        };
+        let body = parse_sub_flow(
+            &children.iter().collect(),
             &node_map,
             &edge_map,
             &children_map,
-            &mut dependency_count_map,
-            None,
-        ),
-        data_outs: end_node.internal_data_ins.as_ref().map(|d| {
-            d.iter()
-                .map(|d| get_data_input(d, end_node.id.clone(), &node_map, &edge_map))
+            &mut scheduled,
+            Some(node.id.clone()),
+            diagnostics,
+        );
+        // inputs of the definition are bound at every call
+        let data_ins = resolve_data(
+            &node.internal_data_ins,
+            &node.id,
+            Direction::In,
+            &node_map,
+            &edge_map,
+            diagnostics,
+        )
+        .map(|ins| {
+            ins.into_iter()
+                .map(|d| DataInOrOut { source: None, ..d })
                 .collect()
-        }),
-        sub_fcs: None,
-    };
+        });
+        sub_fcs.push(SubFC {
+            id: node.id.clone(),
+            name: node.typ.clone(),
+            data_ins,
+            body,
+            data_outs: resolve_data(
+                &node.internal_data_outs,
+                &node.id,
+                Direction::Out,
+                &node_map,
+                &edge_map,
+                diagnostics,
+            ),
+            properties: node.properties.clone(),
+            constraints: node.constraints.clone(),
+        });
+    }
+
+    Some(ApolloYaml {
+        start_id: start_node.id.clone(),
+        end_id: end_node.id.clone(),
+        name: workflow.name,
+        sub_fcs: if sub_fcs.is_empty() {
+            None
+        } else {
+            Some(sub_fcs)
+        },
+        data_ins: resolve_data(
+            &start_node.internal_data_outs,
+            &start_node.id,
+            Direction::In,
+            &node_map,
+            &edge_map,
+            diagnostics,
+        ),
+        workflow_body,
+        data_outs: resolve_data(
+            &end_node.internal_data_ins,
+            &end_node.id,
+            Direction::Out,
+            &node_map,
+            &edge_map,
+            diagnostics,
+        ),
+    })
 }
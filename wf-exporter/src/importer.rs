@@ -0,0 +1,877 @@
+use std::collections::HashMap;
+
+use serde_yaml::Value;
+
+use crate::{
+    exporter::{ApolloYaml, Condition, DataInOrOut, ExportedFunction, SubFC},
+    utils::scalar_to_string,
+    Condition as EditorCondition, Conditions, Data, Edge, EditorNode, IfDataOut,
+    InternalDataInOrOut, InternalDataLoop, InternalIterator, InternalLoopCounter, InternalSection,
+    NodeInternals, NodeStyle, OperandKind, Position, SwitchCase, Workflow,
+};
+
+const NODE_WIDTH: f64 = 300.0;
+const NODE_HEIGHT: f64 = 130.0;
+const COMPOUND_HEADER: f64 = 80.0;
+const SPACING: f64 = 40.0;
+
+/// Width and height of an imported node or body.
+type Size = (f64, f64);
+
+/// Data that later nodes can reference as `<node name>/<data name>`.
+#[derive(Clone)]
+struct Producer {
+    data_id: String,
+    /// Innermost editor node creating the data, as stored in `InternalDataInOrOut.source`.
+    source: String,
+    /// Node and handle the edge towards a consumer starts from.
+    handle_node: String,
+    handle: String,
+}
+
+/// Where the control flow of a sequential body starts.
+struct Entry {
+    node: String,
+    handle: Option<String>,
+}
+
+struct Importer {
+    nodes: Vec<EditorNode>,
+    edges: Vec<Edge>,
+    producers: HashMap<String, Producer>,
+    iterator_types: HashMap<String, Option<String>>,
+    /// Sub-FCs that have not been called yet.
+    sub_fcs: HashMap<String, SubFC>,
+    id_count: usize,
+}
+
+impl Importer {
+    fn new_id(&mut self, prefix: &str) -> String {
+        self.id_count += 1;
+        format!("{}-{}", prefix, self.id_count)
+    }
+
+    fn add_edge(&mut self, source: &str, source_handle: &str, target: &str, target_handle: &str) {
+        let id = format!(
+            "reactflow__edge-{}{}-{}{}",
+            source, source_handle, target, target_handle
+        );
+        if !self.edges.iter().any(|e| e.id == id) {
+            self.edges.push(Edge {
+                id,
+                source: source.to_string(),
+                source_handle: source_handle.to_string(),
+                target: target.to_string(),
+                target_handle: target_handle.to_string(),
+            });
+        }
+    }
+
+    fn register(&mut self, node_name: &str, data_name: &str, producer: Producer) {
+        self.producers
+            .insert(format!("{}/{}", node_name, data_name), producer);
+    }
+
+    fn import_data_ins(
+        &mut self,
+        node_id: &str,
+        data_ins: &[DataInOrOut],
+    ) -> Vec<InternalDataInOrOut> {
+        data_ins
+            .iter()
+            .map(|d| {
+                let producer = d
+                    .source
+                    .as_ref()
+                    .and_then(|s| self.producers.get(s))
+                    .cloned();
+                match producer {
+                    Some(p) => {
+                        if let Some(element_type) = d
+                            .source
+                            .as_ref()
+                            .and_then(|s| self.iterator_types.get_mut(s))
+                        {
+                            element_type.get_or_insert(d.typ.clone());
+                        }
+                        self.add_edge(&p.handle_node, &p.handle, node_id, "idefault");
+                        InternalDataInOrOut {
+                            id: p.data_id,
+                            name: None,
+                            rename: Some(d.name.clone()),
+                            source: Some(p.source),
+                            start_source: None,
+                            value: None,
+                            typ: None,
+                            constraints: d.constraints.clone(),
+                            properties: d.properties.clone(),
+                        }
+                    }
+                    None => InternalDataInOrOut {
+                        id: self.new_id("data"),
+                        name: Some(d.name.clone()),
+                        rename: None,
+                        source: Some(node_id.to_string()),
+                        start_source: None,
+                        value: d.source.clone(),
+                        typ: Some(d.typ.clone()),
+                        constraints: d.constraints.clone(),
+                        properties: d.properties.clone(),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Makes the inputs of a compound node available to its children under `<compound>/<data>`.
+    /// Branching compounds (if, switch, parallel) have no input handles, so their children are
+    /// wired to the original producer but keep reading the data from the compound. Loop
+    /// bodies are wired to the input handles of the loop.
+    fn register_compound_inputs(
+        &mut self,
+        node_id: &str,
+        node_name: &str,
+        data_ins: &[DataInOrOut],
+        internal: &[InternalDataInOrOut],
+        branching: bool,
+    ) {
+        for (idx, (d, internal)) in data_ins.iter().zip(internal).enumerate() {
+            let outer = d
+                .source
+                .as_ref()
+                .and_then(|s| self.producers.get(s))
+                .cloned();
+            let (source, handle_node, handle) = match outer {
+                Some(p) if branching => (node_id.to_string(), p.handle_node, p.handle),
+                _ => (
+                    internal.source.clone().unwrap_or(node_id.to_string()),
+                    node_id.to_string(),
+                    format!("i{}", idx),
+                ),
+            };
+            self.register(
+                node_name,
+                &d.name,
+                Producer {
+                    data_id: internal.id.clone(),
+                    source,
+                    handle_node,
+                    handle,
+                },
+            );
+        }
+    }
+
+    /// Maps the outputs of a loop compound onto the data produced in its body.
+    fn import_loop_data_outs(
+        &mut self,
+        node_id: &str,
+        node_name: &str,
+        data_outs: &[DataInOrOut],
+    ) -> Vec<InternalDataInOrOut> {
+        data_outs
+            .iter()
+            .enumerate()
+            .map(|(idx, d)| {
+                let producer = d
+                    .source
+                    .as_ref()
+                    .and_then(|s| self.producers.get(s))
+                    .cloned();
+                let internal = match producer {
+                    Some(p) => {
+                        self.add_edge(&p.handle_node, &p.handle, node_id, "oidefault");
+                        InternalDataInOrOut {
+                            id: p.data_id,
+                            name: None,
+                            rename: Some(d.name.clone()),
+                            source: Some(p.source),
+                            start_source: None,
+                            value: None,
+                            typ: None,
+                            constraints: d.constraints.clone(),
+                            properties: d.properties.clone(),
+                        }
+                    }
+                    None => InternalDataInOrOut {
+                        id: self.new_id("data"),
+                        name: Some(d.name.clone()),
+                        rename: None,
+                        source: Some(node_id.to_string()),
+                        start_source: None,
+                        value: d.source.clone(),
+                        typ: Some(d.typ.clone()),
+                        constraints: d.constraints.clone(),
+                        properties: d.properties.clone(),
+                    },
+                };
+                self.register(
+                    node_name,
+                    &d.name,
+                    Producer {
+                        data_id: internal.id.clone(),
+                        source: internal.source.clone().unwrap_or(node_id.to_string()),
+                        handle_node: node_id.to_string(),
+                        handle: format!("o{}", idx),
+                    },
+                );
+                internal
+            })
+            .collect()
+    }
+
+    /// Imports a sequential body and returns the size it occupies.
+    fn import_body(
+        &mut self,
+        body: &[ExportedFunction],
+        parent: Option<&str>,
+        entry: Option<Entry>,
+        origin: Position,
+    ) -> Size {
+        let mut siblings: Vec<String> = vec![];
+        let mut width: f64 = 0.0;
+        let mut height: f64 = 0.0;
+
+        for function in body {
+            let position = Position {
+                x: origin.x,
+                y: origin.y + height,
+            };
+            let Some((id, (w, h))) = self.import_function(function, parent, position) else {
+                continue;
+            };
+
+            if let Some(entry) = &entry {
+                let reached = self.edges.iter().any(|e| {
+                    e.target == id
+                        && (siblings.contains(&e.source)
+                            || (e.source == entry.node
+                                && entry.handle.as_ref().is_none_or(|h| *h == e.source_handle)))
+                });
+                if !reached {
+                    let (source, handle) = match siblings.last() {
+                        Some(previous) => (previous.clone(), "o0".to_string()),
+                        None => (
+                            entry.node.clone(),
+                            entry.handle.clone().unwrap_or("o0".to_string()),
+                        ),
+                    };
+                    self.add_edge(&source, &handle, &id, "idefault");
+                }
+            }
+
+            siblings.push(id);
+            width = width.max(w);
+            height += h + SPACING;
+        }
+
+        (width, (height - SPACING).max(0.0))
+    }
+
+    fn import_function(
+        &mut self,
+        function: &ExportedFunction,
+        parent: Option<&str>,
+        position: Position,
+    ) -> Option<(String, Size)> {
+        let node = match function {
+            ExportedFunction::AtomicFunction { node }
+            | ExportedFunction::IfThenElse { node, .. }
+            | ExportedFunction::Switch { node, .. }
+            | ExportedFunction::Parallel { node, .. }
+            | ExportedFunction::ParallelFor { node, .. }
+            | ExportedFunction::SequentialWhile { node, .. }
+            | ExportedFunction::SequentialFor { node, .. } => node,
+            ExportedFunction::StartOrEnd => return None,
+        };
+
+        // the first call of a sub-FC becomes its definition, later ones stay functions
+        let sub_fc = match function {
+            ExportedFunction::AtomicFunction { node } => self.sub_fcs.remove(&node.typ),
+            _ => None,
+        };
+
+        let id = self.new_id("node");
+        let typ = match function {
+            _ if sub_fc.is_some() => "subFC",
+            ExportedFunction::AtomicFunction { .. } => "function",
+            ExportedFunction::IfThenElse { .. } => "if",
+            ExportedFunction::Switch { .. } => "switch",
+            ExportedFunction::Parallel { .. } => "parallelSections",
+            ExportedFunction::ParallelFor { .. } => "parallel",
+            ExportedFunction::SequentialFor { .. } => "for",
+            _ => "while",
+        };
+        let index = self.nodes.len();
+        self.nodes.push(EditorNode {
+            id: id.clone(),
+            typ: typ.to_string(),
+            position,
+            style: None,
+            parent_node: parent.map(|p| p.to_string()),
+            extent: parent.map(|_| "parent".to_string()),
+            data: NodeInternals {
+                name: node.name.clone(),
+                function_type: if typ == "function" || typ == "subFC" {
+                    node.typ.clone()
+                } else {
+                    typ.to_string()
+                },
+                constraints: node.constraints.clone(),
+                properties: node.properties.clone(),
+                ..Default::default()
+            },
+        });
+
+        let empty = vec![];
+        let data_ins = node.data_ins.as_ref().unwrap_or(&empty);
+        let data_outs = node.data_outs.as_ref().unwrap_or(&empty);
+        let mut internal_ins = self.import_data_ins(&id, data_ins);
+        let body_origin = Position {
+            x: SPACING,
+            y: COMPOUND_HEADER,
+        };
+
+        let size = match function {
+            ExportedFunction::IfThenElse {
+                condition,
+                then,
+                or_else,
+                ..
+            } => {
+                self.register_compound_inputs(&id, &node.name, data_ins, &internal_ins, true);
+                let (then_width, then_height) = self.import_body(
+                    then,
+                    Some(&id),
+                    Some(Entry {
+                        node: id.clone(),
+                        handle: Some("true".to_string()),
+                    }),
+                    body_origin.clone(),
+                );
+                let (else_width, else_height) = self.import_body(
+                    or_else,
+                    Some(&id),
+                    Some(Entry {
+                        node: id.clone(),
+                        handle: Some("false".to_string()),
+                    }),
+                    Position {
+                        x: body_origin.x + then_width + SPACING,
+                        y: body_origin.y,
+                    },
+                );
+
+                let (branch_outs, if_data_outs) =
+                    self.import_branch_data_outs(&id, &node.name, data_outs);
+                let conditions = self.import_conditions(condition);
+                let data = &mut self.nodes[index].data;
+                data.conditions = Some(Conditions::List(conditions));
+                data.data_outs = Some(branch_outs);
+                data.if_data_outs = Some(if_data_outs);
+
+                (
+                    then_width + else_width + 3.0 * SPACING,
+                    then_height.max(else_height),
+                )
+            }
+            ExportedFunction::Switch {
+                data_eval,
+                cases,
+                default,
+                ..
+            } => {
+                self.register_compound_inputs(&id, &node.name, data_ins, &internal_ins, true);
+                let mut width = body_origin.x;
+                let mut height: f64 = 0.0;
+                let mut editor_cases = vec![];
+                for case in cases {
+                    let case_id = self.new_id("case");
+                    let (w, h) = self.import_body(
+                        &case.functions,
+                        Some(&id),
+                        Some(Entry {
+                            node: id.clone(),
+                            handle: Some(case_id.clone()),
+                        }),
+                        Position {
+                            x: width,
+                            y: body_origin.y,
+                        },
+                    );
+                    width += w + SPACING;
+                    height = height.max(h);
+                    editor_cases.push(SwitchCase {
+                        id: case_id,
+                        value: case.value.clone(),
+                    });
+                }
+                let (w, h) = self.import_body(
+                    default,
+                    Some(&id),
+                    Some(Entry {
+                        node: id.clone(),
+                        handle: Some("default".to_string()),
+                    }),
+                    Position {
+                        x: width,
+                        y: body_origin.y,
+                    },
+                );
+                width += w + SPACING;
+                height = height.max(h);
+
+                // dataEval either names an input of the switch or refers to outer data,
+                // which then becomes an additional input
+                let own_input = data_eval.source.as_ref().and_then(|source| {
+                    data_ins.iter().position(|d| {
+                        *source == format!("{}/{}", node.name, d.name)
+                            || d.source.as_ref() == Some(source)
+                    })
+                });
+                let switch_data = match own_input {
+                    Some(idx) => internal_ins[idx].id.clone(),
+                    None => {
+                        let added = self.import_data_ins(&id, std::slice::from_ref(data_eval));
+                        let data_id = added[0].id.clone();
+                        internal_ins.extend(added);
+                        data_id
+                    }
+                };
+
+                let (branch_outs, if_data_outs) =
+                    self.import_branch_data_outs(&id, &node.name, data_outs);
+                let data = &mut self.nodes[index].data;
+                data.switch_data = Some(switch_data);
+                data.cases = Some(editor_cases);
+                data.data_outs = Some(branch_outs);
+                data.if_data_outs = Some(if_data_outs);
+                (width, height)
+            }
+            ExportedFunction::Parallel { parallel_body, .. } => {
+                self.register_compound_inputs(&id, &node.name, data_ins, &internal_ins, true);
+                let mut width = body_origin.x;
+                let mut height: f64 = 0.0;
+                let mut sections = vec![];
+                for section in parallel_body {
+                    let section_id = self.new_id("section");
+                    let (w, h) = self.import_body(
+                        &section.section,
+                        Some(&id),
+                        Some(Entry {
+                            node: id.clone(),
+                            handle: Some(section_id.clone()),
+                        }),
+                        Position {
+                            x: width,
+                            y: body_origin.y,
+                        },
+                    );
+                    width += w + SPACING;
+                    height = height.max(h);
+                    sections.push(InternalSection { id: section_id });
+                }
+                let internal_outs = self.import_loop_data_outs(&id, &node.name, data_outs);
+
+                let data = &mut self.nodes[index].data;
+                data.data_outs = Some(internal_outs);
+                data.sections = Some(sections);
+                (width, height)
+            }
+            ExportedFunction::ParallelFor {
+                iterators,
+                loop_counter,
+                loop_body,
+                ..
+            } => {
+                self.register_compound_inputs(&id, &node.name, data_ins, &internal_ins, false);
+                for iterator in iterators.as_ref().unwrap_or(&vec![]) {
+                    self.iterator_types
+                        .insert(format!("{}/{}", node.name, iterator), None);
+                }
+                let body_size = self.import_body(loop_body, Some(&id), None, body_origin);
+                let internal_outs = self.import_loop_data_outs(&id, &node.name, data_outs);
+
+                let mut internal_iterators = vec![];
+                for iterator in iterators.as_ref().unwrap_or(&vec![]) {
+                    internal_iterators.push(InternalIterator {
+                        id: self.new_id("iterator"),
+                        elemnt_type: self
+                            .iterator_types
+                            .remove(&format!("{}/{}", node.name, iterator))
+                            .flatten(),
+                        name: Some(iterator.clone()),
+                    });
+                }
+
+                let data = &mut self.nodes[index].data;
+                data.data_outs = Some(internal_outs);
+                data.iterators = Some(internal_iterators);
+                data.loop_counter = loop_counter.as_ref().map(|lc| InternalLoopCounter {
+                    enabled: true,
+                    name: lc.name.clone(),
+                    from: lc.from.clone(),
+                    to: Some(lc.to.clone()),
+                    typ: lc.typ.clone(),
+                    step: Some(lc.step.clone()),
+                });
+                (body_size.0 + 2.0 * SPACING, body_size.1)
+            }
+            ExportedFunction::SequentialWhile {
+                condition,
+                loop_body,
+                ..
+            } => {
+                self.register_compound_inputs(&id, &node.name, data_ins, &internal_ins, false);
+                let body_size = self.import_body(loop_body, Some(&id), None, body_origin);
+                let internal_outs = self.import_loop_data_outs(&id, &node.name, data_outs);
+
+                let conditions = self.import_conditions(condition);
+                let data = &mut self.nodes[index].data;
+                data.data_outs = Some(internal_outs);
+                data.conditions = Some(Conditions::List(conditions));
+                (body_size.0 + 2.0 * SPACING, body_size.1)
+            }
+            ExportedFunction::SequentialFor {
+                loop_counter,
+                data_loops,
+                loop_body,
+                ..
+            } => {
+                self.register_compound_inputs(&id, &node.name, data_ins, &internal_ins, false);
+                let data_loops = data_loops.as_deref().unwrap_or(&[]);
+                let mut internal_loops = vec![];
+                for (idx, data_loop) in data_loops.iter().enumerate() {
+                    let init_source = data_loop
+                        .init_source
+                        .as_ref()
+                        .map(|source| self.import_reference(&id, source, "idefault"));
+                    let loop_id = self.new_id("data");
+                    self.register(
+                        &node.name,
+                        &data_loop.name,
+                        Producer {
+                            data_id: loop_id.clone(),
+                            source: id.clone(),
+                            handle_node: id.clone(),
+                            handle: format!("l{}", idx),
+                        },
+                    );
+                    internal_loops.push(InternalDataLoop {
+                        id: loop_id,
+                        name: data_loop.name.clone(),
+                        typ: Some(data_loop.typ.clone()),
+                        init_source,
+                        loop_source: None,
+                        value: data_loop.value.clone(),
+                    });
+                }
+
+                let body_size = self.import_body(loop_body, Some(&id), None, body_origin);
+                for (data_loop, internal) in data_loops.iter().zip(&mut internal_loops) {
+                    internal.loop_source = data_loop
+                        .loop_source
+                        .as_ref()
+                        .map(|source| self.import_reference(&id, source, "oidefault"));
+                }
+                let internal_outs = self.import_loop_data_outs(&id, &node.name, data_outs);
+
+                let data = &mut self.nodes[index].data;
+                data.data_outs = Some(internal_outs);
+                data.data_loops = Some(internal_loops);
+                data.loop_counter = Some(InternalLoopCounter {
+                    enabled: true,
+                    name: loop_counter.name.clone(),
+                    from: loop_counter.from.clone(),
+                    to: Some(loop_counter.to.clone()),
+                    typ: loop_counter.typ.clone(),
+                    step: Some(loop_counter.step.clone()),
+                });
+                (body_size.0 + 2.0 * SPACING, body_size.1)
+            }
+            _ => match &sub_fc {
+                Some(sub_fc) => {
+                    let sub_fc_ins = sub_fc.data_ins.as_deref().unwrap_or(&[]);
+                    self.register_compound_inputs(
+                        &id,
+                        &sub_fc.name,
+                        sub_fc_ins,
+                        &internal_ins,
+                        false,
+                    );
+                    let body_size = self.import_body(&sub_fc.body, Some(&id), None, body_origin);
+                    let internal_outs = self.import_loop_data_outs(
+                        &id,
+                        &node.name,
+                        sub_fc.data_outs.as_deref().unwrap_or(&[]),
+                    );
+                    self.nodes[index].data.data_outs = Some(internal_outs);
+                    (body_size.0 + 2.0 * SPACING, body_size.1)
+                }
+                None => {
+                    let mut internal_outs = vec![];
+                    for (idx, d) in data_outs.iter().enumerate() {
+                        let data_id = self.new_id("data");
+                        self.register(
+                            &node.name,
+                            &d.name,
+                            Producer {
+                                data_id: data_id.clone(),
+                                source: id.clone(),
+                                handle_node: id.clone(),
+                                handle: format!("o{}", idx),
+                            },
+                        );
+                        internal_outs.push(InternalDataInOrOut {
+                            id: data_id,
+                            name: Some(d.name.clone()),
+                            rename: None,
+                            source: None,
+                            start_source: None,
+                            value: None,
+                            typ: Some(d.typ.clone()),
+                            constraints: d.constraints.clone(),
+                            properties: d.properties.clone(),
+                        });
+                    }
+                    self.nodes[index].data.data_outs = Some(internal_outs);
+                    (NODE_WIDTH, NODE_HEIGHT)
+                }
+            },
+        };
+
+        self.nodes[index].data.data_ins = Some(internal_ins);
+        let size = if typ == "function" {
+            size
+        } else {
+            let size = (size.0.max(NODE_WIDTH), size.1 + COMPOUND_HEADER + SPACING);
+            self.nodes[index].style = Some(NodeStyle {
+                width: size.0,
+                height: size.1,
+            });
+            size
+        };
+
+        Some((id, size))
+    }
+
+    /// References data from a node without adding it to its inputs or outputs. Sources
+    /// that match no producer are kept as constants of the node.
+    fn import_reference(
+        &mut self,
+        node_id: &str,
+        source: &str,
+        target_handle: &str,
+    ) -> InternalDataInOrOut {
+        match self.producers.get(source).cloned() {
+            Some(p) => {
+                self.add_edge(&p.handle_node, &p.handle, node_id, target_handle);
+                InternalDataInOrOut {
+                    id: p.data_id,
+                    name: None,
+                    rename: None,
+                    source: Some(p.source),
+                    start_source: None,
+                    value: None,
+                    typ: None,
+                    constraints: None,
+                    properties: None,
+                }
+            }
+            None => InternalDataInOrOut {
+                id: self.new_id("data"),
+                name: None,
+                rename: None,
+                source: Some(node_id.to_string()),
+                start_source: None,
+                value: Some(source.to_string()),
+                typ: None,
+                constraints: None,
+                properties: None,
+            },
+        }
+    }
+
+    /// Maps the merged outputs of an if or switch node onto the outputs of its branches.
+    fn import_branch_data_outs(
+        &mut self,
+        id: &str,
+        node_name: &str,
+        data_outs: &[DataInOrOut],
+    ) -> (Vec<InternalDataInOrOut>, Vec<IfDataOut>) {
+        let mut branch_outs: Vec<InternalDataInOrOut> = vec![];
+        let mut if_data_outs = vec![];
+        for (idx, d) in data_outs.iter().enumerate() {
+            let mut sources = vec![];
+            for source in d.source.as_deref().unwrap_or("").split(',') {
+                if let Some(p) = self.producers.get(source.trim()).cloned() {
+                    self.add_edge(&p.handle_node, &p.handle, id, "oidefault");
+                    if !branch_outs.iter().any(|o| o.id == p.data_id) {
+                        branch_outs.push(InternalDataInOrOut {
+                            id: p.data_id.clone(),
+                            name: None,
+                            rename: None,
+                            source: Some(p.source),
+                            start_source: None,
+                            value: None,
+                            typ: None,
+                            constraints: None,
+                            properties: None,
+                        });
+                    }
+                    sources.push(p.data_id);
+                }
+            }
+            let data_id = self.new_id("data");
+            self.register(
+                node_name,
+                &d.name,
+                Producer {
+                    data_id: data_id.clone(),
+                    source: id.to_string(),
+                    handle_node: id.to_string(),
+                    handle: format!("o{}", idx),
+                },
+            );
+            if_data_outs.push(IfDataOut {
+                id: data_id,
+                name: d.name.clone(),
+                typ: d.typ.clone(),
+                sources,
+            });
+        }
+        (branch_outs, if_data_outs)
+    }
+
+    /// AFCL does not mark references, only strings like `node/output` can be one. Those
+    /// naming no imported data, e.g. a loop counter, are left for the export to decide.
+    fn operand_kind(&self, data: &Value) -> Option<OperandKind> {
+        match data {
+            Value::String(s) if self.producers.contains_key(s) => Some(OperandKind::Reference),
+            Value::String(s) if s.contains('/') => None,
+            _ => Some(OperandKind::Literal),
+        }
+    }
+
+    fn import_conditions(&mut self, conditions: &[Condition]) -> Vec<EditorCondition> {
+        conditions
+            .iter()
+            .map(|c| EditorCondition {
+                id: Some(self.new_id("condition")),
+                data1: scalar_to_string(&c.data1),
+                data2: scalar_to_string(&c.data2),
+                data1_kind: self.operand_kind(&c.data1),
+                data2_kind: self.operand_kind(&c.data2),
+                typ: Some(c.typ.clone()),
+                operator: c.operator.as_str().to_string(),
+                negation: Some(c.negation),
+                combined_with: Some(c.combined_with.as_str().to_string()),
+            })
+            .collect()
+    }
+}
+
+/// Rebuilds the editor graph of an AFCL workflow, so hand-written workflows can be
+/// opened in the GUI.
+pub fn import_to_flow(yaml: ApolloYaml) -> Workflow {
+    let mut importer = Importer {
+        nodes: vec![],
+        edges: vec![],
+        producers: HashMap::new(),
+        iterator_types: HashMap::new(),
+        sub_fcs: yaml
+            .sub_fcs
+            .iter()
+            .flatten()
+            .map(|sub_fc| (sub_fc.name.clone(), sub_fc.clone()))
+            .collect(),
+        id_count: 0,
+    };
+
+    let start_outs: Vec<InternalDataInOrOut> = yaml
+        .data_ins
+        .as_ref()
+        .unwrap_or(&vec![])
+        .iter()
+        .enumerate()
+        .map(|(idx, d)| {
+            let data_id = importer.new_id("data");
+            importer.register(
+                &yaml.name,
+                &d.name,
+                Producer {
+                    data_id: data_id.clone(),
+                    source: "0".to_string(),
+                    handle_node: "0".to_string(),
+                    handle: format!("o{}", idx),
+                },
+            );
+            InternalDataInOrOut {
+                id: data_id,
+                name: Some(d.name.clone()),
+                rename: None,
+                source: None,
+                start_source: d.source.clone(),
+                value: None,
+                typ: Some(d.typ.clone()),
+                constraints: d.constraints.clone(),
+                properties: d.properties.clone(),
+            }
+        })
+        .collect();
+
+    importer.nodes.push(EditorNode {
+        id: "0".to_string(),
+        typ: "start".to_string(),
+        position: Position::default(),
+        style: None,
+        parent_node: None,
+        extent: None,
+        data: NodeInternals {
+            name: yaml.name.clone(),
+            function_type: "start".to_string(),
+            data_outs: Some(start_outs),
+            ..Default::default()
+        },
+    });
+
+    let (_, body_height) = importer.import_body(
+        &yaml.workflow_body,
+        None,
+        Some(Entry {
+            node: "0".to_string(),
+            handle: None,
+        }),
+        Position {
+            x: 0.0,
+            y: NODE_HEIGHT + SPACING,
+        },
+    );
+
+    let end_ins = importer.import_data_ins("end", yaml.data_outs.as_ref().unwrap_or(&vec![]));
+    importer.nodes.push(EditorNode {
+        id: "end".to_string(),
+        typ: "end".to_string(),
+        position: Position {
+            x: 0.0,
+            y: NODE_HEIGHT + body_height + 2.0 * SPACING,
+        },
+        style: None,
+        parent_node: None,
+        extent: None,
+        data: NodeInternals {
+            name: "end".to_string(),
+            function_type: "end".to_string(),
+            data_ins: Some(end_ins),
+            ..Default::default()
+        },
+    });
+
+    Workflow {
+        name: yaml.name,
+        data: Data {
+            nodes: importer.nodes,
+            edges: importer.edges,
+        },
+    }
+}
@@ -0,0 +1,1104 @@
+//! Runs an exported workflow locally, without Apollo. Atomic functions are looked up by their
+//! type in a table of implementations, so a workflow can be tried out with mocked functions
+//! before it is deployed. Data is passed around as JSON values, keyed like AFCL sources
+//! (`function/output`).
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use serde_json::{Map, Value};
+use serde_yaml::Value as YamlValue;
+
+use crate::{
+    error::RunError,
+    exporter::{
+        collect_nodes, ApolloYaml, CombineWith, Condition, ConditionOperator, DataInOrOut,
+        DataLoop, ExportedFunction, LoopCounter, Node, SubFC,
+    },
+    trace::{LoopFrame, LoopKind, Recorder, Trace},
+    utils::scalar_to_string,
+    PropertyOrConstraint,
+};
+
+/// Data in- or outputs of a single function call by name.
+pub type Data = Map<String, Value>;
+
+/// Values of all data produced so far, keyed by `function/data`.
+type Env = HashMap<String, Value>;
+
+/// Behaviour of an atomic function type. An `Err` stops the run with
+/// [`RunError::FunctionFailed`].
+pub trait FunctionImpl: Send + Sync {
+    fn call(&self, inputs: &Data) -> Result<Data, String>;
+}
+
+impl<F> FunctionImpl for F
+where
+    F: Fn(&Data) -> Result<Data, String> + Send + Sync,
+{
+    fn call(&self, inputs: &Data) -> Result<Data, String> {
+        self(inputs)
+    }
+}
+
+/// Sub-FCs calling each other deeper than this most likely recurse without end.
+const MAX_SUB_FC_DEPTH: usize = 64;
+
+/// Input of the demo functions that only slows them down.
+const WAIT_TIME: &str = "waitTimeIn";
+
+pub struct Interpreter {
+    functions: HashMap<String, Box<dyn FunctionImpl>>,
+    max_iterations: usize,
+    workers: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    /// An interpreter knowing the function types of the demo workflows: `Addition`,
+    /// `Subtraction`, `SumCollection` and `Collection`.
+    pub fn new() -> Self {
+        let mut interpreter = Interpreter {
+            functions: HashMap::new(),
+            max_iterations: 10_000,
+            workers: 1,
+        };
+        interpreter
+            .register("Addition", addition)
+            .register("Subtraction", subtraction)
+            .register("SumCollection", sum_collection)
+            .register("Collection", collection);
+        interpreter
+    }
+
+    /// Uses `implementation` for all functions of type `function_type`, replacing the
+    /// previous one.
+    pub fn register(
+        &mut self,
+        function_type: &str,
+        implementation: impl FunctionImpl + 'static,
+    ) -> &mut Self {
+        self.functions
+            .insert(function_type.to_string(), Box::new(implementation));
+        self
+    }
+
+    /// Stops loops running more than `max_iterations` times with
+    /// [`RunError::IterationLimit`]. Defaults to 10000.
+    pub fn set_max_iterations(&mut self, max_iterations: usize) -> &mut Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Runs up to `workers` iterations of a parallel for at the same time, each on its own
+    /// thread. Parallel fors nested in an iteration run one iteration after another, so no
+    /// more than `workers` functions are called at once. Defaults to 1.
+    pub fn set_workers(&mut self, workers: usize) -> &mut Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Runs `workflow` and returns its data outputs by name. `input` holds the workflow data
+    /// inputs, keyed by their `source`, the names given in the start node of the editor.
+    pub fn run(&self, workflow: &ApolloYaml, input: &Data) -> Result<Data, RunError> {
+        self.run_with(workflow, input, None)
+    }
+
+    /// Runs `workflow` like [`run`](Self::run) and records every function call, including
+    /// those of a run that failed.
+    pub fn run_traced(
+        &self,
+        workflow: &ApolloYaml,
+        input: &Data,
+    ) -> (Result<Data, RunError>, Trace) {
+        let recorder = Recorder::default();
+        let result = self.run_with(workflow, input, Some(&recorder));
+        let trace = Trace {
+            workflow: workflow.name.clone(),
+            input: input.clone(),
+            outputs: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            events: recorder.into_events(),
+        };
+        (result, trace)
+    }
+
+    fn run_with(
+        &self,
+        workflow: &ApolloYaml,
+        input: &Data,
+        trace: Option<&Recorder>,
+    ) -> Result<Data, RunError> {
+        let mut env = Env::new();
+        for data_in in workflow.data_ins.iter().flatten() {
+            let name = data_in.source.as_deref().unwrap_or(&data_in.name);
+            let value = input.get(name).ok_or_else(|| RunError::MissingInput {
+                name: name.to_string(),
+            })?;
+            env.insert(key(&workflow.name, &data_in.name), value.clone());
+        }
+
+        let run = Run {
+            interpreter: self,
+            sub_fcs: workflow.sub_fcs.as_deref().unwrap_or_default(),
+            depth: 0,
+            workers: self.workers,
+            trace,
+            loops: &[],
+        };
+        run.body(&workflow.workflow_body, &mut env)?;
+        run.outputs(&workflow.name, workflow.data_outs.iter().flatten(), &env)
+    }
+}
+
+/// State of a run that stays the same while descending into compounds.
+struct Run<'a> {
+    interpreter: &'a Interpreter,
+    sub_fcs: &'a [SubFC],
+    depth: usize,
+    /// Iterations of a parallel for that may run at the same time.
+    workers: usize,
+    trace: Option<&'a Recorder>,
+    /// Loops around the functions run, only kept when tracing.
+    loops: &'a [LoopFrame],
+}
+
+impl Run<'_> {
+    fn body(&self, functions: &[ExportedFunction], env: &mut Env) -> Result<(), RunError> {
+        for function in functions {
+            self.function(function, env)?;
+        }
+        Ok(())
+    }
+
+    fn function(&self, function: &ExportedFunction, env: &mut Env) -> Result<(), RunError> {
+        match function {
+            ExportedFunction::AtomicFunction { node } => {
+                let inputs = self.inputs(node, env)?;
+                let outputs = self.call(node, &inputs)?;
+                bind_results(node, &outputs, env)
+            }
+            ExportedFunction::IfThenElse {
+                node,
+                condition,
+                then,
+                or_else,
+            } => {
+                self.bind_inputs(node, env)?;
+                let branch = if self.evaluate(condition, &node.name, env)? {
+                    then
+                } else {
+                    or_else
+                };
+                forget(&[then, or_else], env);
+                self.body(branch, env)?;
+                self.bind_outputs(node, env)
+            }
+            ExportedFunction::Switch {
+                node,
+                data_eval,
+                cases,
+                default,
+            } => {
+                self.bind_inputs(node, env)?;
+                let value = self.input_value(data_eval, &node.name, env)?;
+                let branch = cases
+                    .iter()
+                    .find(|case| matches_case(&value, &case.value, &data_eval.typ))
+                    .map_or(default, |case| &case.functions);
+                let mut branches: Vec<&Vec<ExportedFunction>> =
+                    cases.iter().map(|case| &case.functions).collect();
+                branches.push(default);
+                forget(&branches, env);
+                self.body(branch, env)?;
+                self.bind_outputs(node, env)
+            }
+            ExportedFunction::Parallel {
+                node,
+                parallel_body,
+            } => {
+                self.bind_inputs(node, env)?;
+                for section in parallel_body {
+                    self.body(&section.section, env)?;
+                }
+                self.bind_outputs(node, env)
+            }
+            ExportedFunction::ParallelFor {
+                node,
+                iterators,
+                loop_counter,
+                loop_body,
+            } => self.parallel_for(
+                node,
+                iterators.as_deref().unwrap_or_default(),
+                loop_counter.as_ref(),
+                loop_body,
+                env,
+            ),
+            ExportedFunction::SequentialWhile {
+                node,
+                condition,
+                loop_body,
+            } => self.sequential_while(node, condition, loop_body, env),
+            ExportedFunction::SequentialFor {
+                node,
+                loop_counter,
+                data_loops,
+                loop_body,
+            } => self.sequential_for(
+                node,
+                loop_counter,
+                data_loops.as_deref().unwrap_or_default(),
+                loop_body,
+                env,
+            ),
+            ExportedFunction::StartOrEnd => Ok(()),
+        }
+    }
+
+    /// Calls a sub-FC or the implementation registered for the type of `node`.
+    fn call(&self, node: &Node, inputs: &Data) -> Result<Data, RunError> {
+        let recorder = match self.trace {
+            Some(recorder) => recorder,
+            None => return self.invoke(node, inputs),
+        };
+        let started = recorder.start();
+        let result = self.invoke(node, inputs);
+        recorder.finish(started, node, self.loops, inputs, &result);
+        result
+    }
+
+    fn invoke(&self, node: &Node, inputs: &Data) -> Result<Data, RunError> {
+        if let Some(sub_fc) = self.sub_fcs.iter().find(|s| s.name == node.typ) {
+            return self.call_sub_fc(sub_fc, &node.name, inputs);
+        }
+        let implementation =
+            self.interpreter
+                .functions
+                .get(&node.typ)
+                .ok_or_else(|| RunError::UnknownFunction {
+                    function: node.name.clone(),
+                    typ: node.typ.clone(),
+                })?;
+        implementation
+            .call(inputs)
+            .map_err(|reason| RunError::FunctionFailed {
+                function: node.name.clone(),
+                reason,
+            })
+    }
+
+    /// Runs the body of a sub-FC on its own, it only sees the data passed to the call.
+    fn call_sub_fc(&self, sub_fc: &SubFC, caller: &str, inputs: &Data) -> Result<Data, RunError> {
+        if self.depth >= MAX_SUB_FC_DEPTH {
+            return Err(RunError::RecursionLimit {
+                function: caller.to_string(),
+            });
+        }
+        let mut env = Env::new();
+        for data_in in sub_fc.data_ins.iter().flatten() {
+            let value = inputs
+                .get(&data_in.name)
+                .ok_or_else(|| RunError::MissingData {
+                    function: caller.to_string(),
+                    reference: key(&sub_fc.name, &data_in.name),
+                })?;
+            env.insert(key(&sub_fc.name, &data_in.name), value.clone());
+        }
+        let run = Run {
+            depth: self.depth + 1,
+            ..*self
+        };
+        run.body(&sub_fc.body, &mut env)?;
+        run.outputs(&sub_fc.name, sub_fc.data_outs.iter().flatten(), &env)
+    }
+
+    /// Runs the body once for every element of the iterators or value of the loop counter.
+    /// Every iteration starts from the data known before the loop, the outputs are
+    /// collected in order of the iterations. If an iteration fails, the error of the first
+    /// failed iteration is returned.
+    fn parallel_for(
+        &self,
+        node: &Node,
+        iterators: &[String],
+        loop_counter: Option<&LoopCounter>,
+        loop_body: &[ExportedFunction],
+        env: &mut Env,
+    ) -> Result<(), RunError> {
+        let mut inputs = vec![];
+        for data_in in node.data_ins.iter().flatten() {
+            let value = self.input_value(data_in, &node.name, env)?;
+            let spread = if iterators.contains(&data_in.name) {
+                Spread::Parts(into_items(value, &node.name)?)
+            } else {
+                match block_distribution(data_in) {
+                    Some(1) => Spread::Parts(into_items(value, &node.name)?),
+                    Some(size) => Spread::Parts(
+                        into_items(value, &node.name)?
+                            .chunks(size.max(1))
+                            .map(|chunk| Value::Array(chunk.to_vec()))
+                            .collect(),
+                    ),
+                    None => Spread::Whole(value),
+                }
+            };
+            inputs.push((&data_in.name, spread));
+        }
+
+        let counter = match loop_counter {
+            Some(counter) => Some((
+                counter.name.as_deref().unwrap_or("counter"),
+                self.counter_values(counter, &node.name, env)?,
+            )),
+            None => None,
+        };
+        let iterations = match &counter {
+            Some((_, values)) => values.len(),
+            // iterators of different length stop with the shortest
+            None => inputs
+                .iter()
+                .filter_map(|(_, spread)| match spread {
+                    Spread::Parts(parts) => Some(parts.len()),
+                    Spread::Whole(_) => None,
+                })
+                .min()
+                .ok_or_else(|| RunError::InvalidData {
+                    function: node.name.clone(),
+                    reason: "parallel for has neither an iterator nor a loop counter".to_string(),
+                })?,
+        };
+
+        let data_outs: Vec<&DataInOrOut> = node
+            .data_outs
+            .iter()
+            .flatten()
+            .filter(|data_out| data_out.source.is_some())
+            .collect();
+        let before: &Env = env;
+        let run_iteration = |run: &Run, iteration: usize| -> Result<Vec<Value>, RunError> {
+            let mut local = before.clone();
+            for (name, spread) in &inputs {
+                let value = match spread {
+                    Spread::Whole(value) => value.clone(),
+                    Spread::Parts(parts) => {
+                        parts
+                            .get(iteration)
+                            .cloned()
+                            .ok_or_else(|| RunError::InvalidData {
+                                function: node.name.clone(),
+                                reason: format!(
+                                    "{} has {} elements but the loop runs {} times",
+                                    name,
+                                    parts.len(),
+                                    iterations
+                                ),
+                            })?
+                    }
+                };
+                local.insert(key(&node.name, name), value);
+            }
+            if let Some((name, values)) = &counter {
+                local.insert(key(&node.name, name), number(values[iteration]));
+            }
+            run.in_loop(node, LoopKind::ParallelFor, iteration, |run| {
+                run.body(loop_body, &mut local)
+            })?;
+            data_outs
+                .iter()
+                .map(|data_out| {
+                    let source = data_out.source.as_deref().unwrap_or_default();
+                    resolve(source, &data_out.typ, &node.name, &local)
+                })
+                .collect()
+        };
+        let results = self.iterate(iterations, run_iteration)?;
+
+        let mut collected = vec![vec![]; data_outs.len()];
+        for outputs in results {
+            for (values, value) in collected.iter_mut().zip(outputs) {
+                values.push(value);
+            }
+        }
+        for (data_out, values) in data_outs.into_iter().zip(collected) {
+            env.insert(key(&node.name, &data_out.name), Value::Array(values));
+        }
+        Ok(())
+    }
+
+    /// Calls `run_iteration` for every iteration, on up to `workers` threads taking the next
+    /// iteration when done with one. Results are in order of the iterations.
+    fn iterate<T: Send>(
+        &self,
+        iterations: usize,
+        run_iteration: impl Fn(&Run, usize) -> Result<T, RunError> + Sync,
+    ) -> Result<Vec<T>, RunError> {
+        let workers = self.workers.min(iterations);
+        if workers <= 1 {
+            return (0..iterations)
+                .map(|iteration| run_iteration(self, iteration))
+                .collect();
+        }
+
+        let worker = Run {
+            workers: 1,
+            ..*self
+        };
+        let next = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let results: Mutex<Vec<Option<Result<T, RunError>>>> =
+            Mutex::new((0..iterations).map(|_| None).collect());
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    while !failed.load(Ordering::SeqCst) {
+                        let iteration = next.fetch_add(1, Ordering::SeqCst);
+                        if iteration >= iterations {
+                            break;
+                        }
+                        let result = run_iteration(&worker, iteration);
+                        if result.is_err() {
+                            failed.store(true, Ordering::SeqCst);
+                        }
+                        results.lock().unwrap()[iteration] = Some(result);
+                    }
+                });
+            }
+        });
+        // after a failure later iterations are not started and have no result
+        results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Runs the body until the condition fails, at least once. The counter holds the number
+    /// of finished iterations. Outputs named like an input replace it for the next iteration.
+    fn sequential_while(
+        &self,
+        node: &Node,
+        condition: &[Condition],
+        loop_body: &[ExportedFunction],
+        env: &mut Env,
+    ) -> Result<(), RunError> {
+        self.bind_inputs(node, env)?;
+        let counter = key(&node.name, "counter");
+        let mut iterations = 0;
+        loop {
+            if iterations == self.interpreter.max_iterations {
+                return Err(RunError::IterationLimit {
+                    function: node.name.clone(),
+                    limit: iterations,
+                });
+            }
+            env.insert(counter.clone(), Value::from(iterations));
+            self.in_loop(node, LoopKind::While, iterations, |run| {
+                run.body(loop_body, env)
+            })?;
+            iterations += 1;
+            self.bind_outputs(node, env)?;
+            env.insert(counter.clone(), Value::from(iterations));
+            if !self.evaluate(condition, &node.name, env)? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn sequential_for(
+        &self,
+        node: &Node,
+        loop_counter: &LoopCounter,
+        data_loops: &[DataLoop],
+        loop_body: &[ExportedFunction],
+        env: &mut Env,
+    ) -> Result<(), RunError> {
+        self.bind_inputs(node, env)?;
+        for data_loop in data_loops {
+            let value = match (&data_loop.init_source, &data_loop.value) {
+                (Some(source), _) => resolve(source, &data_loop.typ, &node.name, env)?,
+                (None, Some(value)) => literal(value, &data_loop.typ),
+                (None, None) => {
+                    return Err(RunError::InvalidData {
+                        function: node.name.clone(),
+                        reason: format!("data loop {} has no initial value", data_loop.name),
+                    })
+                }
+            };
+            env.insert(key(&node.name, &data_loop.name), value);
+        }
+
+        let counter = key(
+            &node.name,
+            loop_counter.name.as_deref().unwrap_or("counter"),
+        );
+        let values = self.counter_values(loop_counter, &node.name, env)?;
+        for (iteration, value) in values.into_iter().enumerate() {
+            env.insert(counter.clone(), number(value));
+            self.in_loop(node, LoopKind::For, iteration, |run| {
+                run.body(loop_body, env)
+            })?;
+            for data_loop in data_loops {
+                if let Some(source) = &data_loop.loop_source {
+                    let value = resolve(source, &data_loop.typ, &node.name, env)?;
+                    env.insert(key(&node.name, &data_loop.name), value);
+                }
+            }
+        }
+        self.bind_outputs(node, env)
+    }
+
+    /// Runs `f` with the iteration `index` of the loop `node` added to the loops of the calls
+    /// it makes.
+    fn in_loop<T>(
+        &self,
+        node: &Node,
+        kind: LoopKind,
+        index: usize,
+        f: impl FnOnce(&Run) -> T,
+    ) -> T {
+        if self.trace.is_none() {
+            return f(self);
+        }
+        let mut loops = self.loops.to_vec();
+        loops.push(LoopFrame::new(node, kind, index));
+        f(&Run {
+            loops: &loops,
+            ..*self
+        })
+    }
+
+    /// Values of a loop counter, from `from` up to but excluding `to`.
+    fn counter_values(
+        &self,
+        counter: &LoopCounter,
+        owner: &str,
+        env: &Env,
+    ) -> Result<Vec<f64>, RunError> {
+        let from = match counter.from.as_deref() {
+            Some(from) => number_expression(from, owner, env)?,
+            None => 0.0,
+        };
+        let to = number_expression(&counter.to, owner, env)?;
+        let step = match counter.step.trim() {
+            "" => 1.0,
+            step => number_expression(step, owner, env)?,
+        };
+        if step <= 0.0 {
+            return Err(RunError::InvalidData {
+                function: owner.to_string(),
+                reason: format!("loop counter step {} is not positive", step),
+            });
+        }
+        let iterations = ((to - from) / step).ceil().max(0.0) as usize;
+        if iterations > self.interpreter.max_iterations {
+            return Err(RunError::IterationLimit {
+                function: owner.to_string(),
+                limit: self.interpreter.max_iterations,
+            });
+        }
+        Ok((0..iterations).map(|i| from + i as f64 * step).collect())
+    }
+
+    /// Combines the conditions from first to last. The `combinedWith` of a condition joins it
+    /// with the next one, as the editor stores it, so that of the last condition is unused.
+    /// An empty condition holds.
+    fn evaluate(&self, conditions: &[Condition], owner: &str, env: &Env) -> Result<bool, RunError> {
+        let mut result: Option<(bool, CombineWith)> = None;
+        for condition in conditions {
+            let holds = compare(condition, owner, env)? != condition.negation;
+            let combined = match result {
+                None => holds,
+                Some((before, CombineWith::And)) => before && holds,
+                Some((before, CombineWith::Or)) => before || holds,
+            };
+            result = Some((combined, condition.combined_with));
+        }
+        Ok(result.is_none_or(|(holds, _)| holds))
+    }
+
+    fn inputs(&self, node: &Node, env: &Env) -> Result<Data, RunError> {
+        let mut inputs = Data::new();
+        for data_in in node.data_ins.iter().flatten() {
+            let value = self.input_value(data_in, &node.name, env)?;
+            inputs.insert(data_in.name.clone(), value);
+        }
+        Ok(inputs)
+    }
+
+    /// Makes the data inputs of a compound available to its children as `compound/input`.
+    fn bind_inputs(&self, node: &Node, env: &mut Env) -> Result<(), RunError> {
+        for (name, value) in self.inputs(node, env)? {
+            env.insert(key(&node.name, &name), value);
+        }
+        Ok(())
+    }
+
+    fn bind_outputs(&self, node: &Node, env: &mut Env) -> Result<(), RunError> {
+        for data_out in node.data_outs.iter().flatten() {
+            if let Some(source) = &data_out.source {
+                let value = resolve(source, &data_out.typ, &node.name, env)?;
+                env.insert(key(&node.name, &data_out.name), value);
+            }
+        }
+        Ok(())
+    }
+
+    fn outputs<'d>(
+        &self,
+        owner: &str,
+        data_outs: impl Iterator<Item = &'d DataInOrOut>,
+        env: &Env,
+    ) -> Result<Data, RunError> {
+        let mut outputs = Data::new();
+        for data_out in data_outs {
+            let source = data_out.source.as_deref().unwrap_or_default();
+            let value = resolve(source, &data_out.typ, owner, env)?;
+            outputs.insert(data_out.name.clone(), value);
+        }
+        Ok(outputs)
+    }
+
+    /// Value of a data input with its constraints applied. Distributions are left to the
+    /// enclosing parallel for.
+    fn input_value(
+        &self,
+        data_in: &DataInOrOut,
+        owner: &str,
+        env: &Env,
+    ) -> Result<Value, RunError> {
+        let source = data_in
+            .source
+            .as_deref()
+            .ok_or_else(|| RunError::InvalidData {
+                function: owner.to_string(),
+                reason: format!("data input {} has no source", data_in.name),
+            })?;
+        let mut value = resolve(source, &data_in.typ, owner, env)?;
+        for constraint in data_in.constraints.iter().flatten() {
+            value = apply_constraint(value, constraint, owner, env)?;
+        }
+        Ok(value)
+    }
+}
+
+/// Data handed to the iterations of a parallel for.
+enum Spread {
+    Whole(Value),
+    Parts(Vec<Value>),
+}
+
+fn key(owner: &str, name: &str) -> String {
+    format!("{}/{}", owner, name)
+}
+
+/// Whether `reference` looks like `function/output` of a function that has data already.
+/// AFCL does not mark references, so anything else is a literal like `s3://bucket/key`.
+fn names_data(reference: &str, env: &Env) -> bool {
+    reference.split_once('/').is_some_and(|(function, _)| {
+        env.keys().any(|key| {
+            key.split_once('/')
+                .is_some_and(|(owner, _)| owner == function)
+        })
+    })
+}
+
+/// Value of a data source. References (`function/output`) must have a value already, if
+/// several are given separated by commas the first with a value is taken, as for the merged
+/// outputs of if and switch compounds. Anything else is a literal of type `typ`.
+fn resolve(source: &str, typ: &str, owner: &str, env: &Env) -> Result<Value, RunError> {
+    if let Some(value) = env.get(source) {
+        return Ok(value.clone());
+    }
+    if !source
+        .split(',')
+        .any(|reference| names_data(reference.trim(), env))
+    {
+        return Ok(literal(source, typ));
+    }
+    source
+        .split(',')
+        .find_map(|reference| env.get(reference.trim()))
+        .cloned()
+        .ok_or_else(|| RunError::MissingData {
+            function: owner.to_string(),
+            reference: source.to_string(),
+        })
+}
+
+/// Parses a constant as written in AFCL. Text that does not fit the type stays a string.
+fn literal(text: &str, typ: &str) -> Value {
+    match typ {
+        "number" => text
+            .trim()
+            .parse::<f64>()
+            .map(number)
+            .unwrap_or_else(|_| Value::String(text.to_string())),
+        "boolean" => match text.trim() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::String(text.to_string()),
+        },
+        "string" => Value::String(text.to_string()),
+        _ => serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string())),
+    }
+}
+
+/// Whole numbers are kept as integers, so `2 + 3` prints as `5` rather than `5.0`.
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < 9_007_199_254_740_992.0 {
+        Value::from(value as i64)
+    } else {
+        serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Textual form used by the string operators and to match switch cases.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A number written in a constraint or loop counter, either literal or a reference.
+fn number_expression(expression: &str, owner: &str, env: &Env) -> Result<f64, RunError> {
+    let expression = expression.trim();
+    let value = env
+        .get(expression)
+        .cloned()
+        .unwrap_or_else(|| literal(expression, "number"));
+    as_number(&value).ok_or_else(|| RunError::InvalidData {
+        function: owner.to_string(),
+        reason: format!("{} is no number", expression),
+    })
+}
+
+fn count_expression(expression: &str, owner: &str, env: &Env) -> Result<usize, RunError> {
+    let value = number_expression(expression, owner, env)?;
+    if value < 0.0 || value.fract() != 0.0 {
+        return Err(RunError::InvalidData {
+            function: owner.to_string(),
+            reason: format!("{} is no valid index or count", expression.trim()),
+        });
+    }
+    Ok(value as usize)
+}
+
+fn into_items(value: Value, owner: &str) -> Result<Vec<Value>, RunError> {
+    match value {
+        Value::Array(items) => Ok(items),
+        other => Err(RunError::InvalidData {
+            function: owner.to_string(),
+            reason: format!("expected a collection, found {}", other),
+        }),
+    }
+}
+
+/// Size of the blocks of a `distribution: BLOCK(n)` constraint.
+fn block_distribution(data_in: &DataInOrOut) -> Option<usize> {
+    let distribution = data_in
+        .constraints
+        .iter()
+        .flatten()
+        .find(|constraint| constraint.name == "distribution")?;
+    distribution
+        .value
+        .trim()
+        .strip_prefix("BLOCK(")?
+        .strip_suffix(')')?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn apply_constraint(
+    value: Value,
+    constraint: &PropertyOrConstraint,
+    owner: &str,
+    env: &Env,
+) -> Result<Value, RunError> {
+    match constraint.name.as_str() {
+        "element-index" => element_index(value, &constraint.value, owner, env),
+        "replicate" => {
+            let times = count_expression(&constraint.value, owner, env)?;
+            Ok(match value {
+                Value::Array(items) => Value::Array(
+                    items
+                        .into_iter()
+                        .flat_map(|item| std::iter::repeat_n(item, times))
+                        .collect(),
+                ),
+                other => Value::Array(vec![other; times]),
+            })
+        }
+        "block" => {
+            let (size, overlap) = constraint
+                .value
+                .split_once(',')
+                .unwrap_or((&constraint.value, "0"));
+            let size = count_expression(size, owner, env)?;
+            let overlap = count_expression(overlap, owner, env)?;
+            if overlap >= size {
+                return Err(RunError::InvalidData {
+                    function: owner.to_string(),
+                    reason: format!("blocks of {} cannot overlap by {}", size, overlap),
+                });
+            }
+            let items = into_items(value, owner)?;
+            let mut blocks = vec![];
+            let mut start = 0;
+            while start < items.len() {
+                let end = (start + size).min(items.len());
+                blocks.push(Value::Array(items[start..end].to_vec()));
+                if end == items.len() {
+                    break;
+                }
+                start += size - overlap;
+            }
+            Ok(Value::Array(blocks))
+        }
+        "split" => {
+            let parts = count_expression(&constraint.value, owner, env)?.max(1);
+            let items = into_items(value, owner)?;
+            let size = items.len().div_ceil(parts).max(1);
+            Ok(Value::Array(
+                items
+                    .chunks(size)
+                    .map(|chunk| Value::Array(chunk.to_vec()))
+                    .collect(),
+            ))
+        }
+        _ => Ok(value),
+    }
+}
+
+/// Selects elements by comma separated indices and `start:stop:step` slices. A single index
+/// selects the element itself, anything else a collection.
+fn element_index(value: Value, spec: &str, owner: &str, env: &Env) -> Result<Value, RunError> {
+    let items = into_items(value, owner)?;
+    let out_of_range = |index: usize| RunError::InvalidData {
+        function: owner.to_string(),
+        reason: format!(
+            "element {} is out of range for a collection of {}",
+            index,
+            items.len()
+        ),
+    };
+    let entries: Vec<&str> = spec.split(',').map(str::trim).collect();
+    if let [entry] = entries.as_slice() {
+        if !entry.contains(':') {
+            let index = count_expression(entry, owner, env)?;
+            return items.get(index).cloned().ok_or_else(|| out_of_range(index));
+        }
+    }
+
+    let mut selected = vec![];
+    for entry in entries {
+        if !entry.contains(':') {
+            let index = count_expression(entry, owner, env)?;
+            selected.push(
+                items
+                    .get(index)
+                    .cloned()
+                    .ok_or_else(|| out_of_range(index))?,
+            );
+            continue;
+        }
+        let mut parts = entry.split(':').map(str::trim);
+        let mut bound = |default: usize| match parts.next() {
+            Some(part) if !part.is_empty() => count_expression(part, owner, env),
+            _ => Ok(default),
+        };
+        let start = bound(0)?;
+        let stop = bound(items.len())?.min(items.len());
+        let step = bound(1)?;
+        if step == 0 {
+            return Err(RunError::InvalidData {
+                function: owner.to_string(),
+                reason: format!("slice {} has a step of 0", entry),
+            });
+        }
+        selected.extend((start..stop).step_by(step).map(|i| items[i].clone()));
+    }
+    Ok(Value::Array(selected))
+}
+
+/// Value of a condition operand. Strings naming data are references, other operands are
+/// literals of the condition type.
+fn operand(data: &YamlValue, typ: &str, owner: &str, env: &Env) -> Result<Value, RunError> {
+    match data {
+        YamlValue::String(text) if names_data(text, env) => {
+            env.get(text).cloned().ok_or_else(|| RunError::MissingData {
+                function: owner.to_string(),
+                reference: text.clone(),
+            })
+        }
+        other => Ok(env
+            .get(&scalar_to_string(other))
+            .cloned()
+            .unwrap_or_else(|| literal(&scalar_to_string(other), typ))),
+    }
+}
+
+/// Numeric value of an operand, collections compare by their length.
+fn condition_number(value: &Value, owner: &str) -> Result<f64, RunError> {
+    match value {
+        Value::Array(items) => Ok(items.len() as f64),
+        other => as_number(other).ok_or_else(|| RunError::InvalidData {
+            function: owner.to_string(),
+            reason: format!("condition compares {} as a number", other),
+        }),
+    }
+}
+
+fn compare(condition: &Condition, owner: &str, env: &Env) -> Result<bool, RunError> {
+    let typ = condition.typ.as_str();
+    let first = operand(&condition.data1, typ, owner, env)?;
+    let second = operand(&condition.data2, typ, owner, env)?;
+    let numbers = || -> Result<(f64, f64), RunError> {
+        Ok((
+            condition_number(&first, owner)?,
+            condition_number(&second, owner)?,
+        ))
+    };
+    let equal = || -> Result<bool, RunError> {
+        if typ == "number" {
+            let (first, second) = numbers()?;
+            Ok(first == second)
+        } else {
+            Ok(first == second || text(&first) == text(&second))
+        }
+    };
+    Ok(match condition.operator {
+        ConditionOperator::Eq => equal()?,
+        ConditionOperator::Neq => !equal()?,
+        ConditionOperator::Gt => numbers().map(|(a, b)| a > b)?,
+        ConditionOperator::Lt => numbers().map(|(a, b)| a < b)?,
+        ConditionOperator::Gte => numbers().map(|(a, b)| a >= b)?,
+        ConditionOperator::Lte => numbers().map(|(a, b)| a <= b)?,
+        ConditionOperator::Contains => match &first {
+            Value::Array(items) => items
+                .iter()
+                .any(|item| item == &second || text(item) == text(&second)),
+            other => text(other).contains(&text(&second)),
+        },
+        ConditionOperator::StartsWith => text(&first).starts_with(&text(&second)),
+        ConditionOperator::EndsWith => text(&first).ends_with(&text(&second)),
+    })
+}
+
+fn matches_case(value: &Value, case: &str, typ: &str) -> bool {
+    text(value) == case
+        || (typ == "number" && as_number(value).is_some() && as_number(value) == case.parse().ok())
+}
+
+/// Binds the results of an atomic function. A function declaring a single output may return
+/// it under any name.
+fn bind_results(node: &Node, outputs: &Data, env: &mut Env) -> Result<(), RunError> {
+    let data_outs = node.data_outs.as_deref().unwrap_or_default();
+    for data_out in data_outs {
+        let value = outputs
+            .get(&data_out.name)
+            .or_else(|| match (data_outs.len(), outputs.len()) {
+                (1, 1) => outputs.values().next(),
+                _ => None,
+            })
+            .ok_or_else(|| RunError::MissingOutput {
+                function: node.name.clone(),
+                output: data_out.name.clone(),
+            })?;
+        env.insert(key(&node.name, &data_out.name), value.clone());
+    }
+    Ok(())
+}
+
+/// Drops the data of every function in the branches, so outputs of a branch taken in an
+/// earlier loop iteration are not mistaken for outputs of this one.
+fn forget(branches: &[&Vec<ExportedFunction>], env: &mut Env) {
+    let mut nodes = vec![];
+    for branch in branches {
+        collect_nodes(branch, &mut nodes);
+    }
+    env.retain(|key, _| {
+        key.rsplit_once('/')
+            .is_none_or(|(owner, _)| !nodes.iter().any(|node| node.name == owner))
+    });
+}
+
+fn output(name: &str, value: Value) -> Data {
+    let mut outputs = Data::new();
+    outputs.insert(name.to_string(), value);
+    outputs
+}
+
+fn number_input(name: &str, value: &Value) -> Result<f64, String> {
+    as_number(value).ok_or_else(|| format!("{} is no number: {}", name, value))
+}
+
+/// Sums all inputs but `waitTimeIn`. Going by position instead of name keeps demos working
+/// that misspell `secondSummand`.
+fn addition(inputs: &Data) -> Result<Data, String> {
+    let mut sum = 0.0;
+    for (name, value) in inputs.iter().filter(|(name, _)| *name != WAIT_TIME) {
+        sum += number_input(name, value)?;
+    }
+    Ok(output("sum", number(sum)))
+}
+
+fn subtraction(inputs: &Data) -> Result<Data, String> {
+    let operand = |name: &str| {
+        inputs
+            .get(name)
+            .ok_or_else(|| format!("{} is missing", name))
+            .and_then(|value| number_input(name, value))
+    };
+    Ok(output(
+        "difference",
+        number(operand("minuend")? - operand("subtrahend")?),
+    ))
+}
+
+fn sum_collection(inputs: &Data) -> Result<Data, String> {
+    let items = inputs
+        .get("collectionToSum")
+        .or_else(|| inputs.values().find(|value| value.is_array()))
+        .and_then(Value::as_array)
+        .ok_or("collectionToSum is missing or no collection")?;
+    let mut sum = 0.0;
+    for item in items {
+        sum += number_input("collectionToSum", item)?;
+    }
+    Ok(output("collectionSum", number(sum)))
+}
+
+/// Gathers all inputs but `waitTimeIn` into one collection, collections are flattened.
+fn collection(inputs: &Data) -> Result<Data, String> {
+    let mut items = vec![];
+    for (_, value) in inputs.iter().filter(|(name, _)| *name != WAIT_TIME) {
+        match value {
+            Value::Array(values) => items.extend(values.iter().cloned()),
+            other => items.push(other.clone()),
+        }
+    }
+    Ok(output("collection", Value::Array(items)))
+}
@@ -1,49 +1,91 @@
-mod exporter;
+pub mod dot;
+pub mod error;
+pub mod explain;
+pub mod exporter;
+pub mod importer;
+pub mod interpreter;
+pub mod lint;
+pub mod logs;
+pub mod runtime;
+pub mod source_map;
+pub mod spec;
+pub mod trace;
 mod utils;
+#[cfg(feature = "wasi")]
+pub mod wasi;
 
-use exporter::export_from_flow;
+use error::{Diagnostics, ExportError};
+use exporter::{export_from_flow, ApolloYaml};
 use gloo_utils::format::JsValueSerdeExt;
+use importer::import_to_flow;
+use lint::lint_workflow;
 use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use source_map::SourceMap;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+use trace::{NodeState, Replay, Trace, TraceEvent};
 use utils::set_panic_hook;
 use wasm_bindgen::prelude::*;
 
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-    fn alert(s: &str);
-}
-
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Workflow {
     name: String,
     data: Data,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 struct Data {
     nodes: Vec<EditorNode>,
     edges: Vec<Edge>,
 }
 
-#[derive(Deserialize)]
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize)]
 struct EditorNode {
     id: String,
     #[serde(rename = "type")]
     typ: String,
+    #[serde(default)]
+    position: Position,
+    #[serde(skip_deserializing)]
+    style: Option<NodeStyle>,
     #[serde(rename = "parentNode")]
     parent_node: Option<String>,
+    extent: Option<String>,
     data: NodeInternals,
 }
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+struct Position {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+struct NodeStyle {
+    width: f64,
+    height: f64,
+}
+
+#[skip_serializing_none]
 #[derive(Deserialize, Serialize, Clone)]
 pub struct InternalLoopCounter {
+    /// Only read for parallel nodes, for nodes always count.
+    #[serde(default)]
     enabled: bool,
+    name: Option<String>,
+    from: Option<String>,
     to: Option<String>,
     #[serde(rename = "type")]
     typ: String,
     step: Option<String>,
 }
 
+#[skip_serializing_none]
 #[derive(Deserialize, Serialize, Clone)]
 pub struct InternalIterator {
     id: String,
@@ -52,7 +94,8 @@ pub struct InternalIterator {
     name: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, Default)]
 struct NodeInternals {
     name: String,
     #[serde(rename = "type")]
@@ -62,12 +105,21 @@ struct NodeInternals {
     #[serde(rename = "dataOuts")]
     data_outs: Option<Vec<InternalDataInOrOut>>,
 
+    /// Merged outputs of if and switch nodes.
     #[serde(rename = "ifDataOuts")]
     if_data_outs: Option<Vec<IfDataOut>>,
-    conditions: Option<Vec<Condition>>,
+    conditions: Option<Conditions>,
     iterators: Option<Vec<InternalIterator>>,
     #[serde(rename = "loopCounter")]
     loop_counter: Option<InternalLoopCounter>,
+    /// Id of the data input a switch node evaluates.
+    #[serde(rename = "switchData")]
+    switch_data: Option<String>,
+    cases: Option<Vec<SwitchCase>>,
+    /// Sections of a parallel node, each started from the handle named after its id.
+    sections: Option<Vec<InternalSection>>,
+    #[serde(rename = "dataLoops")]
+    data_loops: Option<Vec<InternalDataLoop>>,
     constraints: Option<Vec<PropertyOrConstraint>>,
     properties: Option<Vec<PropertyOrConstraint>>,
 }
@@ -75,6 +127,7 @@ struct NodeInternals {
 #[derive(Deserialize, Serialize, Clone)]
 pub struct PropertyOrConstraint {
     name: String,
+    #[serde(deserialize_with = "utils::deserialize_scalar")]
     value: String,
 }
 
@@ -87,10 +140,85 @@ pub struct IfDataOut {
     sources: Vec<String>,
 }
 
-#[derive(Deserialize)]
+/// A case of a switch node. Its children are connected to the handle named after `id`.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SwitchCase {
+    id: String,
+    #[serde(deserialize_with = "utils::deserialize_scalar")]
+    value: String,
+}
+
+/// Data a for node carries from one iteration to the next. Children read it like data
+/// produced by the for node, using the id of the data loop.
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, Clone)]
+pub struct InternalDataLoop {
+    id: String,
+    name: String,
+    #[serde(rename = "type")]
+    typ: Option<String>,
+    /// Data the first iteration starts with, referenced like a data input of the for node.
+    #[serde(rename = "initSource")]
+    init_source: Option<InternalDataInOrOut>,
+    /// Data of the loop body passed to the next iteration, referenced like a data output.
+    #[serde(rename = "loopSource")]
+    loop_source: Option<InternalDataInOrOut>,
+    value: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct InternalSection {
+    id: String,
+}
+
+/// Conditions of an if or while node. The editor stores a list in which every condition
+/// names how it is joined with the next one, nested groups can only be written by hand or
+/// by other tools producing graphs.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Conditions {
+    Group(ConditionGroup),
+    List(Vec<Condition>),
+}
+
+/// Conditions and nested groups combined with the same `and` or `or`. A negated group
+/// negates the combined result.
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize)]
+pub struct ConditionGroup {
+    id: Option<String>,
+    #[serde(rename = "combinedWith")]
+    combined_with: String,
+    negation: Option<bool>,
+    conditions: Vec<ConditionTerm>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ConditionTerm {
+    Group(ConditionGroup),
+    Condition(Condition),
+}
+
+/// Whether a condition operand names data like `node/output` or is a value itself.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OperandKind {
+    Reference,
+    Literal,
+}
+
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize)]
 pub struct Condition {
+    id: Option<String>,
     data1: String,
     data2: String,
+    /// Unset operands containing a `/` are references if they name data, literals otherwise.
+    #[serde(rename = "data1Kind")]
+    data1_kind: Option<OperandKind>,
+    #[serde(rename = "data2Kind")]
+    data2_kind: Option<OperandKind>,
     #[serde(rename = "type")]
     typ: Option<String>,
     operator: String,
@@ -99,7 +227,8 @@ pub struct Condition {
     combined_with: Option<String>,
 }
 
-#[derive(Deserialize, Clone)]
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct InternalDataInOrOut {
     id: String,
     name: Option<String>,
@@ -114,8 +243,10 @@ pub struct InternalDataInOrOut {
     properties: Option<Vec<PropertyOrConstraint>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 struct Edge {
+    #[serde(default)]
+    id: String,
     source: String,
     #[serde(rename = "sourceHandle")]
     source_handle: String,
@@ -124,20 +255,255 @@ struct Edge {
     target_handle: String,
 }
 
+/// Parses a saved editor graph. Graphs downloaded from the GUI start with a BOM.
+pub fn parse_workflow(json: &str) -> Result<Workflow, ExportError> {
+    serde_json::from_str(json.trim_start_matches('\u{feff}')).map_err(|e| {
+        ExportError::InvalidInput {
+            reason: e.to_string(),
+        }
+    })
+}
+
+/// Parses AFCL yaml, as exported by [`export_to_yaml`] or written by hand.
+pub fn parse_afcl(yaml: &str) -> Result<ApolloYaml, ExportError> {
+    serde_yaml::from_str(yaml.trim_start_matches('\u{feff}')).map_err(|e| {
+        ExportError::InvalidInput {
+            reason: e.to_string(),
+        }
+    })
+}
+
+/// Adds `path` to `files`, or if it is a directory every .json file below it, in order of
+/// their paths. Used by the command line tools to find graphs and specs.
+pub fn collect_json_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+        for entry in entries {
+            if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "json") {
+                collect_json_files(&entry, files)?;
+            }
+        }
+    } else {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+/// Exports an editor graph to AFCL yaml. `None` if any error was reported, including lints
+/// with error severity. The graph is exported even then, so all problems show up at once.
+pub fn export_to_yaml(workflow: Workflow, diagnostics: &mut Diagnostics) -> Option<String> {
+    export_to_afcl(workflow, diagnostics)
+        .map(|apollo_yaml| serde_yaml::to_string(&apollo_yaml).unwrap())
+}
+
+/// Like [`export_to_yaml`], but also maps the yaml back to the nodes of the graph.
+pub fn export_to_yaml_with_source_map(
+    workflow: Workflow,
+    diagnostics: &mut Diagnostics,
+) -> Option<(String, SourceMap)> {
+    export_to_afcl(workflow, diagnostics).map(|apollo_yaml| {
+        let yaml = serde_yaml::to_string(&apollo_yaml).unwrap();
+        let source_map = SourceMap::build(&apollo_yaml, &yaml);
+        (yaml, source_map)
+    })
+}
+
+/// Like [`export_to_yaml`], but keeps the workflow as [`ApolloYaml`], e.g. to run it with the
+/// [`interpreter`].
+pub fn export_to_afcl(workflow: Workflow, diagnostics: &mut Diagnostics) -> Option<ApolloYaml> {
+    diagnostics.lints.extend(lint_workflow(&workflow));
+    export_from_flow(workflow, diagnostics).filter(|_| !diagnostics.has_errors())
+}
+
+#[skip_serializing_none]
+#[derive(Serialize)]
+struct ExportResponse {
+    yaml: String,
+    #[serde(rename = "sourceMap")]
+    source_map: Option<SourceMap>,
+    #[serde(flatten)]
+    diagnostics: Diagnostics,
+}
+
+#[derive(Serialize)]
+struct LogOverlayResponse {
+    overlay: Option<logs::Overlay>,
+    #[serde(flatten)]
+    diagnostics: Diagnostics,
+    /// Why the log could not be read.
+    #[serde(rename = "logError")]
+    log_error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ReplayResponse<'t> {
+    steps: usize,
+    position: usize,
+    /// The call started or ended by the last step.
+    current: Option<&'t TraceEvent>,
+    nodes: BTreeMap<String, NodeState>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DotResponse {
+    dot: String,
+    #[serde(flatten)]
+    diagnostics: Diagnostics,
+}
+
+#[derive(Serialize)]
+struct ImportResponse {
+    workflow: Option<Workflow>,
+    #[serde(flatten)]
+    diagnostics: Diagnostics,
+}
+
+/// Returns `{ yaml, errors, warnings, lints }`. `yaml` is empty as soon as there is an error.
+/// With `source_map` the response also has the `sourceMap` of the yaml, with `explain` the
+/// `explanations` of all data sources, see [`explain`].
+#[wasm_bindgen]
+pub fn convert_to_wf_yaml(wf: JsValue, source_map: Option<bool>, explain: Option<bool>) -> JsValue {
+    set_panic_hook();
+
+    let mut diagnostics = if explain.unwrap_or(false) {
+        Diagnostics::explaining()
+    } else {
+        Diagnostics::default()
+    };
+    let (yaml, source_map) = match wf.into_serde::<Workflow>() {
+        Ok(workflow) if source_map.unwrap_or(false) => {
+            match export_to_yaml_with_source_map(workflow, &mut diagnostics) {
+                Some((yaml, source_map)) => (yaml, Some(source_map)),
+                None => (String::new(), None),
+            }
+        }
+        Ok(workflow) => (
+            export_to_yaml(workflow, &mut diagnostics).unwrap_or_default(),
+            None,
+        ),
+        Err(e) => {
+            diagnostics.errors.push(ExportError::InvalidInput {
+                reason: e.to_string(),
+            });
+            (String::new(), None)
+        }
+    };
+
+    JsValue::from_serde(&ExportResponse {
+        yaml,
+        source_map,
+        diagnostics,
+    })
+    .unwrap()
+}
+
+/// Returns `{ dot, errors, warnings, lints }` with the editor graph rendered as Graphviz DOT,
+/// or with `exported` the AFCL tree it is exported to, see [`dot`]. `dot` is empty if the
+/// graph cannot be read or exported.
 #[wasm_bindgen]
-pub fn convert_to_wf_yaml(wf: JsValue) -> String {
+pub fn convert_to_dot(wf: JsValue, exported: Option<bool>) -> JsValue {
     set_panic_hook();
 
+    let mut diagnostics = Diagnostics::default();
+    let dot = match wf.into_serde::<Workflow>() {
+        Ok(workflow) if exported.unwrap_or(false) => export_to_afcl(workflow, &mut diagnostics)
+            .map(|apollo_yaml| dot::afcl_to_dot(&apollo_yaml))
+            .unwrap_or_default(),
+        Ok(workflow) => dot::workflow_to_dot(&workflow),
+        Err(e) => {
+            diagnostics.errors.push(ExportError::InvalidInput {
+                reason: e.to_string(),
+            });
+            String::new()
+        }
+    };
+
+    JsValue::from_serde(&DotResponse { dot, diagnostics }).unwrap()
+}
+
+/// Returns `{ workflow, errors, warnings }` with the editor graph of an AFCL workflow.
+#[wasm_bindgen]
+pub fn convert_from_wf_yaml(yaml: String) -> JsValue {
+    set_panic_hook();
+
+    let mut diagnostics = Diagnostics::default();
+    let workflow = parse_afcl(&yaml)
+        .map(import_to_flow)
+        .map_err(|e| diagnostics.errors.push(e))
+        .ok();
+
+    JsValue::from_serde(&ImportResponse {
+        workflow,
+        diagnostics,
+    })
+    .unwrap()
+}
+
+/// Returns `{ steps, position, current, nodes, error }`: the state of every node after `step`
+/// steps of a trace recorded by `wf-run --trace`. Nodes are keyed by their editor id.
+#[wasm_bindgen]
+pub fn replay_trace(trace: JsValue, step: usize) -> JsValue {
+    set_panic_hook();
+
+    let trace = match trace.into_serde::<Trace>() {
+        Ok(trace) => trace,
+        Err(e) => {
+            return JsValue::from_serde(&ReplayResponse {
+                steps: 0,
+                position: 0,
+                current: None,
+                nodes: BTreeMap::new(),
+                error: Some(e.to_string()),
+            })
+            .unwrap()
+        }
+    };
+    let mut replay = Replay::new(&trace);
+    replay.seek(step);
+    JsValue::from_serde(&ReplayResponse {
+        steps: replay.steps(),
+        position: replay.position(),
+        current: replay.current(),
+        nodes: replay.state(),
+        error: None,
+    })
+    .unwrap()
+}
+
+/// Returns `{ overlay, logError, errors, warnings, lints }`. `overlay` has the logged calls of
+/// every node of the editor graph `wf`, see [`logs`] for the layout of `log`. It is `null` if
+/// the graph cannot be exported or the log cannot be read.
+#[wasm_bindgen]
+pub fn log_overlay(wf: JsValue, log: String) -> JsValue {
+    set_panic_hook();
+
+    let mut diagnostics = Diagnostics::default();
+    let mut log_error = None;
     let workflow = match wf.into_serde::<Workflow>() {
-        Ok(wf) => wf,
+        Ok(workflow) => export_to_afcl(workflow, &mut diagnostics),
         Err(e) => {
-            log(&e.to_string());
-            alert(
-                "Error! Could not parse workflow. Please check the console for more information.",
-            );
-            return String::from("");
+            diagnostics.errors.push(ExportError::InvalidInput {
+                reason: e.to_string(),
+            });
+            None
         }
     };
+    let overlay = workflow.and_then(|workflow| match logs::parse_log(&log) {
+        Ok(records) => Some(logs::overlay(&workflow, &records)),
+        Err(e) => {
+            log_error = Some(e.to_string());
+            None
+        }
+    });
 
-    return serde_yaml::to_string(&export_from_flow(workflow)).unwrap();
+    JsValue::from_serde(&LogOverlayResponse {
+        overlay,
+        diagnostics,
+        log_error,
+    })
+    .unwrap()
 }
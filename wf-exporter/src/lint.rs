@@ -0,0 +1,266 @@
+//! Checks run over the editor graph before it is exported. Every rule has a stable id and a
+//! severity, so the GUI can list all findings at once and point to the affected nodes.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use crate::{EditorNode, Workflow};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// The exported workflow would be invalid, the export is stopped.
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    UnreachableNode,
+    EmptyFunctionType,
+    MissingDataSource,
+    DuplicateName,
+    UnwiredEndInput,
+    ParallelWithoutIterator,
+}
+
+impl Rule {
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::UnreachableNode => "unreachableNode",
+            Rule::EmptyFunctionType => "emptyFunctionType",
+            Rule::MissingDataSource => "missingDataSource",
+            Rule::DuplicateName => "duplicateName",
+            Rule::UnwiredEndInput => "unwiredEndInput",
+            Rule::ParallelWithoutIterator => "parallelWithoutIterator",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Rule::UnreachableNode => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// A single finding of a rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub rule: Rule,
+    pub node_id: String,
+    pub data_id: Option<String>,
+    pub message: String,
+}
+
+impl Lint {
+    fn new(rule: Rule, node: &EditorNode, message: String) -> Self {
+        Lint {
+            rule,
+            node_id: node.id.clone(),
+            data_id: None,
+            message,
+        }
+    }
+
+    fn with_data(mut self, data_id: &str) -> Self {
+        self.data_id = Some(data_id.to_string());
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.rule.severity()
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Serialize for Lint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Lint", 5)?;
+        state.serialize_field("rule", self.rule.id())?;
+        state.serialize_field("severity", &self.severity())?;
+        state.serialize_field("nodeId", &self.node_id)?;
+        state.serialize_field("dataId", &self.data_id)?;
+        state.serialize_field("message", &self.message)?;
+        state.end()
+    }
+}
+
+/// Runs every rule over the graph. Findings are ordered by rule, then by the order of the nodes.
+pub fn lint_workflow(workflow: &Workflow) -> Vec<Lint> {
+    let nodes = &workflow.data.nodes;
+    let mut lints = vec![];
+    unreachable_nodes(workflow, &mut lints);
+    for node in nodes {
+        if matches!(node.typ.as_str(), "function" | "subFC")
+            && node.data.function_type.trim().is_empty()
+        {
+            lints.push(Lint::new(
+                Rule::EmptyFunctionType,
+                node,
+                format!("function {} has no type", node.data.name),
+            ));
+        }
+    }
+    for node in nodes
+        .iter()
+        .filter(|n| !matches!(n.typ.as_str(), "start" | "end"))
+    {
+        for data_in in node.data.data_ins.iter().flatten() {
+            if data_in.source.is_none() {
+                lints.push(
+                    Lint::new(
+                        Rule::MissingDataSource,
+                        node,
+                        format!(
+                            "data input {} of {} is not connected",
+                            data_in.name.as_deref().unwrap_or(&data_in.id),
+                            node.data.name
+                        ),
+                    )
+                    .with_data(&data_in.id),
+                );
+            }
+        }
+    }
+    duplicate_names(nodes, &mut lints);
+    for node in nodes.iter().filter(|n| n.typ == "end") {
+        for data_in in node.data.data_ins.iter().flatten() {
+            if data_in.source.is_none() {
+                lints.push(
+                    Lint::new(
+                        Rule::UnwiredEndInput,
+                        node,
+                        format!(
+                            "workflow output {} is not connected",
+                            data_in.name.as_deref().unwrap_or(&data_in.id)
+                        ),
+                    )
+                    .with_data(&data_in.id),
+                );
+            }
+        }
+    }
+    for node in nodes.iter().filter(|n| n.typ == "parallel") {
+        let has_iterator = node
+            .data
+            .iterators
+            .iter()
+            .flatten()
+            .any(|iterator| iterator.name.as_deref().is_some_and(|n| !n.is_empty()));
+        let counts = node
+            .data
+            .loop_counter
+            .as_ref()
+            .is_some_and(|counter| counter.enabled);
+        if !has_iterator && !counts {
+            lints.push(Lint::new(
+                Rule::ParallelWithoutIterator,
+                node,
+                format!(
+                    "parallel for {} has neither an iterator nor a loop counter",
+                    node.data.name
+                ),
+            ));
+        }
+    }
+    lints
+}
+
+/// Compounds whose children all run, as opposed to branches started from a handle.
+fn runs_all_children(typ: &str) -> bool {
+    matches!(typ, "while" | "for" | "parallel" | "subFC")
+}
+
+/// Reports nodes the exporter never visits from the start node. Children of loops run with
+/// their parent, children of branching compounds only when connected to a branch handle or
+/// to another node of the branch.
+fn unreachable_nodes(workflow: &Workflow, lints: &mut Vec<Lint>) {
+    let nodes: HashMap<&str, &EditorNode> = workflow
+        .data
+        .nodes
+        .iter()
+        .map(|node| (node.id.as_str(), node))
+        .collect();
+    let is_inside = |node: &str, ancestor: &str| {
+        let mut current = nodes.get(node).and_then(|n| n.parent_node.as_deref());
+        while let Some(parent) = current {
+            if parent == ancestor {
+                return true;
+            }
+            current = nodes.get(parent).and_then(|n| n.parent_node.as_deref());
+        }
+        false
+    };
+
+    let mut reached: HashSet<&str> = HashSet::from(["0"]);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for node in &workflow.data.nodes {
+            if reached.contains(node.id.as_str()) {
+                continue;
+            }
+            let parent = node.parent_node.as_deref();
+            let by_parent = parent.is_some_and(|parent| {
+                reached.contains(parent)
+                    && nodes.get(parent).is_some_and(|p| runs_all_children(&p.typ))
+            });
+            let by_edge = || {
+                workflow.data.edges.iter().any(|edge| {
+                    edge.target == node.id
+                        && reached.contains(edge.source.as_str())
+                        && parent.is_none_or(|parent| {
+                            edge.source == parent || is_inside(&edge.source, parent)
+                        })
+                })
+            };
+            if by_parent || by_edge() {
+                reached.insert(&node.id);
+                changed = true;
+            }
+        }
+    }
+
+    for node in &workflow.data.nodes {
+        if !reached.contains(node.id.as_str()) {
+            lints.push(Lint::new(
+                Rule::UnreachableNode,
+                node,
+                format!(
+                    "{} is never reached from the start node and is left out",
+                    node.data.name
+                ),
+            ));
+        }
+    }
+}
+
+/// Reports nodes sharing a name with an earlier node of the same compound.
+fn duplicate_names(nodes: &[EditorNode], lints: &mut Vec<Lint>) {
+    let mut seen: HashSet<(Option<&str>, &str)> = HashSet::new();
+    for node in nodes
+        .iter()
+        .filter(|n| !matches!(n.typ.as_str(), "start" | "end"))
+    {
+        if !seen.insert((node.parent_node.as_deref(), node.data.name.as_str())) {
+            lints.push(Lint::new(
+                Rule::DuplicateName,
+                node,
+                format!(
+                    "{} is used by more than one node in the same scope",
+                    node.data.name
+                ),
+            ));
+        }
+    }
+}
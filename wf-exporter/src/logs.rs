@@ -0,0 +1,205 @@
+//! Reads the function logs of a workflow run on Apollo and maps them back to the editor
+//! nodes, e.g. to show where a run spent its time as a heatmap over the graph.
+//!
+//! A log has one record per function call. It is either JSON, an array of records or one
+//! record per line:
+//!
+//! ```json
+//! [
+//!   { "function": "add", "start": 1700000000000, "end": 1700000000250 },
+//!   { "function": "add", "start": 1700000000300, "end": 1700000000420, "iteration": 1 },
+//!   { "function": "split", "start": 1700000000500, "end": 1700000000510,
+//!     "success": false, "error": "timeout" }
+//! ]
+//! ```
+//!
+//! or CSV with a header naming the columns, in any order:
+//!
+//! ```text
+//! function,start,end,success,iteration,error
+//! add,1700000000000,1700000000250,true,0,
+//! add,1700000000300,1700000000420,true,1,
+//! split,1700000000500,1700000000510,false,,timeout
+//! ```
+//!
+//! `function` is the AFCL name of the function, `start` and `end` are milliseconds since the
+//! Unix epoch. `success` defaults to true. `iteration` is the index of the loop iteration or
+//! parallel for element of the call, calls retried after a failure log the same index again.
+//! Other fields and columns are ignored.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::LogError, exporter::ApolloYaml};
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct LogRecord {
+    pub function: String,
+    pub start: f64,
+    pub end: f64,
+    pub success: Option<bool>,
+    pub iteration: Option<usize>,
+    pub error: Option<String>,
+}
+
+impl LogRecord {
+    pub fn failed(&self) -> bool {
+        self.success == Some(false)
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    Json,
+    Csv,
+}
+
+impl LogFormat {
+    /// JSON if the log starts with an array or object, CSV otherwise.
+    pub fn detect(log: &str) -> Self {
+        match log
+            .trim_start_matches('\u{feff}')
+            .trim_start()
+            .chars()
+            .next()
+        {
+            Some('[') | Some('{') => LogFormat::Json,
+            _ => LogFormat::Csv,
+        }
+    }
+}
+
+/// Parses a log in the format it is detected to be in, see [`LogFormat::detect`].
+pub fn parse_log(log: &str) -> Result<Vec<LogRecord>, LogError> {
+    let log = log.trim_start_matches('\u{feff}');
+    let records = match LogFormat::detect(log) {
+        LogFormat::Json => parse_json(log)?,
+        LogFormat::Csv => parse_csv(log)?,
+    };
+    if let Some((index, record)) = records
+        .iter()
+        .enumerate()
+        .find(|(_, record)| record.end < record.start)
+    {
+        return Err(LogError::NegativeDuration {
+            function: record.function.clone(),
+            record: index + 1,
+        });
+    }
+    Ok(records)
+}
+
+fn parse_json(log: &str) -> Result<Vec<LogRecord>, LogError> {
+    let invalid = |e: serde_json::Error| LogError::InvalidJson {
+        reason: e.to_string(),
+    };
+    if log.trim_start().starts_with('[') {
+        return serde_json::from_str(log).map_err(invalid);
+    }
+    serde_json::Deserializer::from_str(log)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .map_err(invalid)
+}
+
+fn parse_csv(log: &str) -> Result<Vec<LogRecord>, LogError> {
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(log.as_bytes())
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .map_err(|e| LogError::InvalidCsv {
+            reason: e.to_string(),
+        })
+}
+
+/// Logged calls of a single node. Durations are in milliseconds.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStats {
+    pub function: String,
+    pub calls: usize,
+    pub failures: usize,
+    /// Loop iterations the node ran in: the distinct `iteration`s logged, or the number of
+    /// calls if none were.
+    pub iterations: usize,
+    pub total_duration: f64,
+    pub min_duration: f64,
+    pub max_duration: f64,
+    pub mean_duration: f64,
+    /// Total duration relative to the node taking longest, from 0 to 1.
+    pub heat: f64,
+}
+
+/// What the editor shows over the graph, nodes are keyed by their editor id.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Overlay {
+    pub nodes: BTreeMap<String, NodeStats>,
+    /// Logged functions the workflow does not have, e.g. because the log is of another
+    /// version of it.
+    pub unmatched: Vec<String>,
+}
+
+/// Joins `records` to the nodes of `workflow`, as exported from the editor. Functions of a
+/// workflow read from AFCL have no editor id and are keyed by their name instead.
+pub fn overlay(workflow: &ApolloYaml, records: &[LogRecord]) -> Overlay {
+    let mut keys: HashMap<&str, &str> = HashMap::new();
+    for node in workflow.nodes() {
+        keys.entry(node.name.as_str())
+            .or_insert_with(|| node.editor_id().unwrap_or(&node.name));
+    }
+
+    let mut calls: BTreeMap<&str, Vec<&LogRecord>> = BTreeMap::new();
+    let mut unmatched = BTreeSet::new();
+    for record in records {
+        match keys.get(record.function.as_str()) {
+            Some(key) => calls.entry(key).or_default().push(record),
+            None => {
+                unmatched.insert(record.function.clone());
+            }
+        }
+    }
+
+    let mut nodes: BTreeMap<String, NodeStats> = calls
+        .into_iter()
+        .map(|(key, records)| (key.to_string(), stats(&records)))
+        .collect();
+    let hottest = nodes
+        .values()
+        .map(|stats| stats.total_duration)
+        .fold(0.0, f64::max);
+    if hottest > 0.0 {
+        for stats in nodes.values_mut() {
+            stats.heat = stats.total_duration / hottest;
+        }
+    }
+    Overlay {
+        nodes,
+        unmatched: unmatched.into_iter().collect(),
+    }
+}
+
+fn stats(records: &[&LogRecord]) -> NodeStats {
+    let durations: Vec<f64> = records.iter().map(|record| record.duration()).collect();
+    let total_duration: f64 = durations.iter().sum();
+    let iterations: BTreeSet<usize> = records.iter().filter_map(|r| r.iteration).collect();
+    NodeStats {
+        function: records[0].function.clone(),
+        calls: records.len(),
+        failures: records.iter().filter(|record| record.failed()).count(),
+        iterations: match iterations.len() {
+            0 => records.len(),
+            distinct => distinct,
+        },
+        total_duration,
+        min_duration: durations.iter().copied().fold(f64::INFINITY, f64::min),
+        max_duration: durations.iter().copied().fold(0.0, f64::max),
+        mean_duration: total_duration / records.len() as f64,
+        heat: 0.0,
+    }
+}
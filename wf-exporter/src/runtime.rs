@@ -0,0 +1,171 @@
+//! Runs atomic functions as local processes, a stand-in for Apollo when debugging a workflow
+//! on a single machine. A JSON config maps function types to commands:
+//!
+//! ```json
+//! {
+//!   "workers": 4,
+//!   "functions": {
+//!     "bwa-aln": { "command": ["python3", "functions/bwa_aln.py"] },
+//!     "Addition": { "command": ["./add.sh"], "cwd": "functions", "env": { "DEBUG": "1" } }
+//!   },
+//!   "modules": {
+//!     "Subtraction": { "module": "functions/subtract.wasm", "fuel": 1000000 }
+//!   }
+//! }
+//! ```
+//!
+//! A call starts the command, writes the data inputs as a JSON object to its stdin and reads
+//! the data outputs as a JSON object from its stdout. Relative paths are resolved against
+//! the directory of the config, which is also the default working directory. `modules` are
+//! WASI modules run in-process the same way, see [`crate::wasi`], which needs the `wasi`
+//! feature.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+};
+
+use serde::Deserialize;
+
+use crate::interpreter::{Data, FunctionImpl, Interpreter};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RuntimeConfig {
+    /// Iterations of a parallel for run at the same time, see [`Interpreter::set_workers`].
+    pub workers: Option<usize>,
+    /// Commands by function type.
+    #[serde(default)]
+    pub functions: HashMap<String, CommandFunction>,
+    /// WASI modules by function type.
+    #[serde(default)]
+    pub modules: HashMap<String, ModuleConfig>,
+}
+
+impl RuntimeConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut config: RuntimeConfig =
+            serde_json::from_str(content.trim_start_matches('\u{feff}'))
+                .map_err(|e| format!("invalid runtime config: {}", e))?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        for function in config.functions.values_mut() {
+            function.resolve_paths(base);
+        }
+        for module in config.modules.values_mut() {
+            module.module = base.join(&module.module);
+        }
+        Ok(config)
+    }
+
+    /// Registers the commands and modules with `interpreter`, replacing built-ins of the same
+    /// type. Fails if a module cannot be compiled.
+    pub fn configure(&self, interpreter: &mut Interpreter) -> Result<(), String> {
+        if let Some(workers) = self.workers {
+            interpreter.set_workers(workers);
+        }
+        for (function_type, function) in &self.functions {
+            interpreter.register(function_type, function.clone());
+        }
+        #[cfg(feature = "wasi")]
+        for (function_type, module) in &self.modules {
+            interpreter.register(
+                function_type,
+                crate::wasi::WasiFunction::load(function_type, module)?,
+            );
+        }
+        #[cfg(not(feature = "wasi"))]
+        if let Some((function_type, module)) = self.modules.iter().next() {
+            return Err(format!(
+                "{} is a WASI module ({}), rebuild with the wasi feature to run it",
+                function_type,
+                module.module.display()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A function type implemented by a local command.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CommandFunction {
+    /// Program and arguments.
+    pub command: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// A function type implemented by a WASI module.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ModuleConfig {
+    pub module: PathBuf,
+    /// The only environment variables the module sees.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Stops a call after this many instructions, roughly, instead of running forever.
+    pub fuel: Option<u64>,
+}
+
+impl CommandFunction {
+    /// Makes the working directory and a program given as relative path relative to `base`.
+    fn resolve_paths(&mut self, base: &Path) {
+        let cwd = base.join(self.cwd.as_deref().unwrap_or_else(|| Path::new("")));
+        if let Some(program) = self.command.first_mut() {
+            // bare names are looked up in PATH
+            if program.contains('/') && Path::new(program).is_relative() {
+                *program = base.join(&*program).to_string_lossy().into_owned();
+            }
+        }
+        self.cwd = Some(cwd);
+    }
+}
+
+impl FunctionImpl for CommandFunction {
+    fn call(&self, inputs: &Data) -> Result<Data, String> {
+        let (program, args) = self.command.split_first().ok_or("command is empty")?;
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .envs(&self.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(cwd);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("could not start {}: {}", program, e))?;
+
+        // written from another thread, the command may fill stdout before reading all input
+        let input = serde_json::to_vec(inputs).map_err(|e| e.to_string())?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or("stdin of the command is not piped")?;
+        let writer = thread::spawn(move || stdin.write_all(&input));
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("{} failed: {}", program, e))?;
+        // a command not reading its inputs closes stdin early, which is fine
+        let _ = writer.join();
+
+        if !output.status.success() {
+            return Err(format!(
+                "{} exited with {}: {}",
+                program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        serde_json::from_slice(&output.stdout)
+            .map_err(|e| format!("{} did not print a JSON object of outputs: {}", program, e))
+    }
+}
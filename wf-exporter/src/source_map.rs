@@ -0,0 +1,278 @@
+//! Links the elements of exported AFCL back to the editor graph. Apollo reports validation
+//! errors and failed functions by their name or position in the yaml, the source map finds
+//! the node and data they belong to, and the other way around.
+//!
+//! Elements are addressed by their path in the yaml, like
+//! `workflowBody[1].while.loopBody[0].function.dataIns[2]`, and by the lines they span.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use yaml_rust2::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker,
+};
+
+use crate::exporter::{ApolloYaml, DataInOrOut, ExportedFunction};
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct SourceMap {
+    /// In order of their lines.
+    pub entries: Vec<SourceMapEntry>,
+}
+
+/// A function, sub-FC or data in- or output of the yaml and the editor node it came from.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMapEntry {
+    pub path: String,
+    /// First and last line of the element, counted from 1.
+    pub line: usize,
+    pub end_line: usize,
+    /// Name of the function, sub-FC or workflow in the yaml.
+    pub function: String,
+    /// Name of the data, for data in- and outputs.
+    pub data: Option<String>,
+    pub node_id: String,
+    pub data_id: Option<String>,
+}
+
+impl SourceMap {
+    /// Maps the elements of `workflow`, as exported from the editor, to their place in
+    /// `yaml`, the serialized `workflow`. Elements without an editor id are left out.
+    pub fn build(workflow: &ApolloYaml, yaml: &str) -> Self {
+        let mut builder = Builder {
+            lines: element_lines(yaml),
+            entries: vec![],
+        };
+        builder.data(
+            "dataIns",
+            workflow.data_ins.as_deref(),
+            &workflow.name,
+            workflow.start_id(),
+        );
+        for (i, sub_fc) in workflow.sub_fcs.iter().flatten().enumerate() {
+            let path = format!("subFCs[{}]", i);
+            builder.push(&path, &sub_fc.name, sub_fc.editor_id(), None);
+            let node_id = sub_fc.editor_id();
+            builder.data(
+                &format!("{}.dataIns", path),
+                sub_fc.data_ins.as_deref(),
+                &sub_fc.name,
+                node_id,
+            );
+            builder.functions(&format!("{}.subFCBody", path), &sub_fc.body);
+            builder.data(
+                &format!("{}.dataOuts", path),
+                sub_fc.data_outs.as_deref(),
+                &sub_fc.name,
+                node_id,
+            );
+        }
+        builder.functions("workflowBody", &workflow.workflow_body);
+        builder.data(
+            "dataOuts",
+            workflow.data_outs.as_deref(),
+            &workflow.name,
+            workflow.end_id(),
+        );
+
+        let mut entries = builder.entries;
+        entries.sort_by_key(|entry| entry.line);
+        SourceMap { entries }
+    }
+
+    /// The innermost element spanning `line`.
+    pub fn at_line(&self, line: usize) -> Option<&SourceMapEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.line <= line && line <= entry.end_line)
+    }
+
+    pub fn at_path(&self, path: &str) -> Option<&SourceMapEntry> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+
+    /// The function named `function`, or its data named `data`, as cited by Apollo.
+    pub fn find(&self, function: &str, data: Option<&str>) -> Option<&SourceMapEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.function == function && entry.data.as_deref() == data)
+    }
+
+    /// Where a node, or one of its data in- or outputs, ended up in the yaml. A data output
+    /// of an if or switch node appears once per branch, the first is returned.
+    pub fn of_node(&self, node_id: &str, data_id: Option<&str>) -> Option<&SourceMapEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.node_id == node_id && entry.data_id.as_deref() == data_id)
+    }
+}
+
+struct Builder {
+    lines: HashMap<String, (usize, usize)>,
+    entries: Vec<SourceMapEntry>,
+}
+
+impl Builder {
+    fn push(
+        &mut self,
+        path: &str,
+        function: &str,
+        node_id: Option<&str>,
+        data: Option<&DataInOrOut>,
+    ) {
+        let (Some(node_id), Some(&(line, end_line))) = (node_id, self.lines.get(path)) else {
+            return;
+        };
+        self.entries.push(SourceMapEntry {
+            path: path.to_string(),
+            line,
+            end_line,
+            function: function.to_string(),
+            data: data.map(|data| data.name.clone()),
+            node_id: node_id.to_string(),
+            data_id: data.and_then(|data| data.editor_id()).map(str::to_string),
+        });
+    }
+
+    fn data(
+        &mut self,
+        path: &str,
+        data: Option<&[DataInOrOut]>,
+        function: &str,
+        node_id: Option<&str>,
+    ) {
+        for (i, data) in data.unwrap_or_default().iter().enumerate() {
+            self.push(&format!("{}[{}]", path, i), function, node_id, Some(data));
+        }
+    }
+
+    fn functions(&mut self, path: &str, functions: &[ExportedFunction]) {
+        for (i, function) in functions.iter().enumerate() {
+            let (Some(node), Some(kind)) = (function.node(), function.kind()) else {
+                continue;
+            };
+            let item = format!("{}[{}]", path, i);
+            let node_id = node.editor_id();
+            self.push(&item, &node.name, node_id, None);
+
+            let inner = format!("{}.{}", item, kind);
+            self.data(
+                &format!("{}.dataIns", inner),
+                node.data_ins.as_deref(),
+                &node.name,
+                node_id,
+            );
+            if let ExportedFunction::Switch { data_eval, .. } = function {
+                self.push(
+                    &format!("{}.dataEval", inner),
+                    &node.name,
+                    node_id,
+                    Some(data_eval),
+                );
+            }
+            for (body_path, body) in function.bodies() {
+                self.functions(&format!("{}.{}", inner, body_path), body);
+            }
+            self.data(
+                &format!("{}.dataOuts", inner),
+                node.data_outs.as_deref(),
+                &node.name,
+                node_id,
+            );
+        }
+    }
+}
+
+/// First and last line of every mapping in `yaml` by path. A yaml that does not parse
+/// yields the mappings before the error.
+fn element_lines(yaml: &str) -> HashMap<String, (usize, usize)> {
+    let mut lines = Lines::default();
+    let _ = Parser::new_from_str(yaml).load(&mut lines, false);
+    lines.elements
+}
+
+#[derive(Default)]
+struct Lines {
+    stack: Vec<Frame>,
+    /// Line of the last scalar, the end of every mapping closed after it.
+    last_line: usize,
+    elements: HashMap<String, (usize, usize)>,
+}
+
+struct Frame {
+    path: String,
+    line: usize,
+    /// `None` for sequences.
+    key: Option<Option<String>>,
+    index: usize,
+}
+
+impl Lines {
+    /// Path of the value starting now in the open mapping or sequence.
+    fn child_path(&mut self) -> String {
+        let Some(parent) = self.stack.last_mut() else {
+            return String::new();
+        };
+        match &mut parent.key {
+            Some(key) => {
+                let key = key.take().unwrap_or_default();
+                if parent.path.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", parent.path, key)
+                }
+            }
+            None => {
+                parent.index += 1;
+                format!("{}[{}]", parent.path, parent.index - 1)
+            }
+        }
+    }
+
+    fn open(&mut self, mark: Marker, mapping: bool) {
+        let path = self.child_path();
+        self.stack.push(Frame {
+            path,
+            line: mark.line(),
+            key: if mapping { Some(None) } else { None },
+            index: 0,
+        });
+    }
+}
+
+impl MarkedEventReceiver for Lines {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        match event {
+            Event::Scalar(value, ..) => {
+                self.last_line = mark.line();
+                match self.stack.last_mut() {
+                    Some(Frame {
+                        key: Some(key @ None),
+                        ..
+                    }) => *key = Some(value),
+                    _ => {
+                        self.child_path();
+                    }
+                }
+            }
+            Event::Alias(..) => {
+                self.child_path();
+            }
+            Event::MappingStart(..) => self.open(mark, true),
+            Event::SequenceStart(..) => self.open(mark, false),
+            Event::MappingEnd => {
+                if let Some(frame) = self.stack.pop() {
+                    let end_line = self.last_line.max(frame.line);
+                    self.elements.insert(frame.path, (frame.line, end_line));
+                }
+            }
+            Event::SequenceEnd => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Test cases for workflows, run with the [`interpreter`](crate::interpreter) instead of
+//! Apollo. A spec is a JSON file naming a workflow, either a saved editor graph or AFCL yaml,
+//! and the cases to run it with:
+//!
+//! ```json
+//! {
+//!   "workflow": "../../../example-wfs/demos/simple_if_no_else.json",
+//!   "mocks": { "Addition": { "outputs": { "sum": 42 } } },
+//!   "cases": [
+//!     {
+//!       "name": "condition holds",
+//!       "input": { "a": 2, "b": 3, "wait": 0, "c1": true, "c2": "abc" },
+//!       "expected": { "wf_result": 42 }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! The workflow path is relative to the spec. Function types without a mock use the built-in
+//! implementations, mocks of a case replace those of the spec.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    error::Diagnostics,
+    export_to_afcl,
+    exporter::ApolloYaml,
+    interpreter::{Data, FunctionImpl, Interpreter},
+    parse_afcl, parse_workflow,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TestSpec {
+    pub workflow: PathBuf,
+    /// Mocks by function type, used by all cases.
+    #[serde(default)]
+    pub mocks: HashMap<String, Mock>,
+    pub cases: Vec<TestCase>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TestCase {
+    pub name: String,
+    /// Workflow inputs, keyed by the names given in the start node.
+    #[serde(default)]
+    pub input: Data,
+    #[serde(default)]
+    pub mocks: HashMap<String, Mock>,
+    /// Workflow outputs to check, outputs left out are not compared.
+    #[serde(default)]
+    pub expected: Data,
+    /// Part of the message of the error the run is expected to stop with.
+    pub error: Option<String>,
+    pub max_iterations: Option<usize>,
+}
+
+/// Behaviour of a mocked function type. A call returns the outputs of the first entry in
+/// `calls` whose inputs it was given, otherwise `outputs`. With `fail` every call fails.
+#[derive(Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Mock {
+    #[serde(default)]
+    pub calls: Vec<MockCall>,
+    pub outputs: Option<Data>,
+    pub fail: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MockCall {
+    /// Inputs the call must be given, others are ignored.
+    pub inputs: Data,
+    pub outputs: Data,
+}
+
+impl FunctionImpl for Mock {
+    fn call(&self, inputs: &Data) -> Result<Data, String> {
+        if let Some(reason) = &self.fail {
+            return Err(reason.clone());
+        }
+        self.calls
+            .iter()
+            .find(|call| {
+                call.inputs
+                    .iter()
+                    .all(|(name, value)| inputs.get(name) == Some(value))
+            })
+            .map(|call| &call.outputs)
+            .or(self.outputs.as_ref())
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "mock has no outputs for the inputs {}",
+                    Value::Object(inputs.clone())
+                )
+            })
+    }
+}
+
+/// Outcome of a single case, `failure` says why it did not pass.
+pub struct CaseResult {
+    pub name: String,
+    pub failure: Option<String>,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Reads the spec at `path` and runs all of its cases. Fails if the spec or its workflow
+/// cannot be read or the workflow cannot be exported.
+pub fn run_spec_file(path: &Path) -> Result<Vec<CaseResult>, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let spec: TestSpec = serde_json::from_str(content.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("invalid spec: {}", e))?;
+    let workflow_path = path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(&spec.workflow);
+    let workflow =
+        load_workflow(&workflow_path).map_err(|e| format!("{}: {}", workflow_path.display(), e))?;
+    Ok(run_spec(&spec, &workflow))
+}
+
+/// Loads a saved editor graph (`.json`) and exports it, or parses AFCL yaml.
+pub fn load_workflow(path: &Path) -> Result<ApolloYaml, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    if path.extension().is_some_and(|ext| ext != "json") {
+        return parse_afcl(&content).map_err(|e| e.to_string());
+    }
+
+    let mut diagnostics = Diagnostics::default();
+    let workflow = parse_workflow(&content).map_err(|e| e.to_string())?;
+    export_to_afcl(workflow, &mut diagnostics).ok_or_else(|| {
+        let mut problems: Vec<String> = diagnostics.errors.iter().map(|e| e.to_string()).collect();
+        problems.extend(diagnostics.lints.iter().map(|lint| lint.to_string()));
+        format!("could not be exported: {}", problems.join(", "))
+    })
+}
+
+pub fn run_spec(spec: &TestSpec, workflow: &ApolloYaml) -> Vec<CaseResult> {
+    spec.cases
+        .iter()
+        .map(|case| CaseResult {
+            name: case.name.clone(),
+            failure: run_case(spec, case, workflow).err(),
+        })
+        .collect()
+}
+
+fn run_case(spec: &TestSpec, case: &TestCase, workflow: &ApolloYaml) -> Result<(), String> {
+    let mut interpreter = Interpreter::new();
+    if let Some(max_iterations) = case.max_iterations {
+        interpreter.set_max_iterations(max_iterations);
+    }
+    for (function_type, mock) in spec.mocks.iter().chain(&case.mocks) {
+        interpreter.register(function_type, mock.clone());
+    }
+
+    let outputs = match (interpreter.run(workflow, &case.input), &case.error) {
+        (Ok(outputs), None) => outputs,
+        (Ok(_), Some(expected)) => {
+            return Err(format!("expected the run to fail with \"{}\"", expected))
+        }
+        (Err(e), Some(expected)) if e.to_string().contains(expected.as_str()) => return Ok(()),
+        (Err(e), _) => return Err(format!("run failed: {}", e)),
+    };
+    let mut differences = vec![];
+    for (name, expected) in &case.expected {
+        match outputs.get(name) {
+            Some(actual) if actual == expected => {}
+            Some(actual) => {
+                differences.push(format!("{} is {}, expected {}", name, actual, expected))
+            }
+            None => differences.push(format!("{} is missing", name)),
+        }
+    }
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(differences.join(", "))
+    }
+}
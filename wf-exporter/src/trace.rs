@@ -0,0 +1,283 @@
+//! Records the function calls of a local run and replays them. Every call becomes a
+//! [`TraceEvent`] keyed by the exported function name and, for workflows exported from the
+//! editor, the id of the editor node. [`Replay`] steps through the starts and ends of the calls
+//! and rebuilds the state of every node, e.g. to show a past run over the graph.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::RunError, exporter::Node, interpreter::Data};
+
+/// A recorded run: what went in, what came out and every call in between.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Trace {
+    pub workflow: String,
+    pub input: Data,
+    pub outputs: Option<Data>,
+    pub error: Option<String>,
+    /// Calls in the order they started.
+    pub events: Vec<TraceEvent>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceEvent {
+    /// Position in the order the calls started.
+    pub seq: usize,
+    pub function: String,
+    pub node_id: Option<String>,
+    #[serde(rename = "type")]
+    pub typ: String,
+    /// Microseconds since the Unix epoch.
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub inputs: Data,
+    /// `None` if the call failed.
+    pub outputs: Option<Data>,
+    pub error: Option<String>,
+    /// Loops enclosing the call, outermost first.
+    pub loops: Vec<LoopFrame>,
+}
+
+impl TraceEvent {
+    /// The editor node id, or the function name for workflows read from AFCL.
+    pub fn key(&self) -> &str {
+        self.node_id.as_deref().unwrap_or(&self.function)
+    }
+
+    /// Iteration of the innermost while or for loop.
+    pub fn iteration(&self) -> Option<usize> {
+        self.loops
+            .iter()
+            .rev()
+            .find(|frame| frame.kind != LoopKind::ParallelFor)
+            .map(|frame| frame.index)
+    }
+
+    /// Element index of the innermost parallel for.
+    pub fn element(&self) -> Option<usize> {
+        self.loops
+            .iter()
+            .rev()
+            .find(|frame| frame.kind == LoopKind::ParallelFor)
+            .map(|frame| frame.index)
+    }
+}
+
+/// A loop around a call and the iteration it was in.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoopFrame {
+    pub function: String,
+    pub node_id: Option<String>,
+    pub kind: LoopKind,
+    /// Iteration of a while or for, element of a parallel for, counted from 0.
+    pub index: usize,
+}
+
+impl LoopFrame {
+    pub(crate) fn new(node: &Node, kind: LoopKind, index: usize) -> Self {
+        LoopFrame {
+            function: node.name.clone(),
+            node_id: node.editor_id().map(str::to_string),
+            kind,
+            index,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LoopKind {
+    While,
+    For,
+    ParallelFor,
+}
+
+/// Collects the events of a run, shared by all workers.
+#[derive(Default)]
+pub(crate) struct Recorder {
+    next: AtomicUsize,
+    events: Mutex<Vec<TraceEvent>>,
+}
+
+/// A call that has started, see [`Recorder::start`].
+pub(crate) struct Started {
+    seq: usize,
+    at: u64,
+}
+
+impl Recorder {
+    pub(crate) fn start(&self) -> Started {
+        Started {
+            seq: self.next.fetch_add(1, Ordering::SeqCst),
+            at: now(),
+        }
+    }
+
+    pub(crate) fn finish(
+        &self,
+        started: Started,
+        node: &Node,
+        loops: &[LoopFrame],
+        inputs: &Data,
+        result: &Result<Data, RunError>,
+    ) {
+        let event = TraceEvent {
+            seq: started.seq,
+            function: node.name.clone(),
+            node_id: node.editor_id().map(str::to_string),
+            typ: node.typ.clone(),
+            started_at: started.at,
+            ended_at: now(),
+            inputs: inputs.clone(),
+            outputs: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            loops: loops.to_vec(),
+        };
+        self.events.lock().unwrap().push(event);
+    }
+
+    pub(crate) fn into_events(self) -> Vec<TraceEvent> {
+        let mut events = self.events.into_inner().unwrap();
+        events.sort_by_key(|event| event.seq);
+        events
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_micros() as u64)
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NodeStatus {
+    Running,
+    Done,
+    Failed,
+}
+
+/// What is known about a node at a step of a replay.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeState {
+    pub status: NodeStatus,
+    /// Finished calls, a node in a loop is called once per iteration.
+    pub calls: usize,
+    /// Inputs, outputs and loops of the latest call.
+    pub inputs: Data,
+    pub outputs: Option<Data>,
+    pub error: Option<String>,
+    pub loops: Vec<LoopFrame>,
+}
+
+/// Steps through a trace. Every call is two steps, its start and its end, ordered by time,
+/// so calls of parallel iterations overlap as they did in the run.
+pub struct Replay<'t> {
+    trace: &'t Trace,
+    /// Event index and whether the step is the end of the call.
+    timeline: Vec<(usize, bool)>,
+    position: usize,
+}
+
+impl<'t> Replay<'t> {
+    /// Starts before the first step.
+    pub fn new(trace: &'t Trace) -> Self {
+        // at the same time calls end before others start, calls that took no time at all
+        // end after everything else
+        let mut steps: Vec<(u64, u8, usize, usize)> = vec![];
+        for (index, event) in trace.events.iter().enumerate() {
+            let ended_at = event.ended_at.max(event.started_at);
+            let end = if ended_at == event.started_at { 2 } else { 0 };
+            steps.push((event.started_at, 1, event.seq, index));
+            steps.push((ended_at, end, event.seq, index));
+        }
+        steps.sort();
+        Replay {
+            trace,
+            timeline: steps
+                .into_iter()
+                .map(|(_, order, _, index)| (index, order != 1))
+                .collect(),
+            position: 0,
+        }
+    }
+
+    pub fn steps(&self) -> usize {
+        self.timeline.len()
+    }
+
+    /// Number of steps taken.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves to `position`, at most the number of steps.
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.steps());
+    }
+
+    /// Takes the next step, `false` at the end of the trace.
+    pub fn forward(&mut self) -> bool {
+        let moved = self.position < self.steps();
+        self.seek(self.position + 1);
+        moved
+    }
+
+    /// Takes back the last step, `false` at the beginning.
+    pub fn back(&mut self) -> bool {
+        let moved = self.position > 0;
+        self.position = self.position.saturating_sub(1);
+        moved
+    }
+
+    /// The call started or ended by the last step.
+    pub fn current(&self) -> Option<&'t TraceEvent> {
+        let (index, _) = self.timeline.get(self.position.checked_sub(1)?)?;
+        self.trace.events.get(*index)
+    }
+
+    /// State of every node called so far, keyed like [`TraceEvent::key`].
+    pub fn state(&self) -> BTreeMap<String, NodeState> {
+        let mut nodes: BTreeMap<String, NodeState> = BTreeMap::new();
+        for &(index, end) in &self.timeline[..self.position] {
+            let event = &self.trace.events[index];
+            let calls = nodes.get(event.key()).map_or(0, |state| state.calls);
+            let state = if end {
+                NodeState {
+                    status: match event.error {
+                        Some(_) => NodeStatus::Failed,
+                        None => NodeStatus::Done,
+                    },
+                    calls: calls + 1,
+                    inputs: event.inputs.clone(),
+                    outputs: event.outputs.clone(),
+                    error: event.error.clone(),
+                    loops: event.loops.clone(),
+                }
+            } else {
+                NodeState {
+                    status: NodeStatus::Running,
+                    calls,
+                    inputs: event.inputs.clone(),
+                    outputs: None,
+                    error: None,
+                    loops: event.loops.clone(),
+                }
+            };
+            nodes.insert(event.key().to_string(), state);
+        }
+        nodes
+    }
+}
@@ -1,4 +1,57 @@
+use serde::{de::Error, Deserialize, Deserializer};
+use serde_yaml::Value;
+
 pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+/// Textual form of a yaml scalar, `null` and collections become their yaml text.
+pub fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+/// Hand-written AFCL often leaves scalars unquoted (`value: 3`, `negation: false`),
+/// so string fields accept any scalar and keep its textual form.
+pub fn deserialize_scalar<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        other => Err(D::Error::custom(format!(
+            "expected a scalar value, found {:?}",
+            other
+        ))),
+    }
+}
+
+/// Accepts `true` as well as `'true'`, older exports wrote booleans as strings.
+pub fn deserialize_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    match deserialize_scalar(deserializer)?.as_str() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => Err(D::Error::custom(format!(
+            "expected a boolean, found {}",
+            other
+        ))),
+    }
+}
+
+pub fn deserialize_optional_scalar<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => deserialize_scalar(value)
+            .map(Some)
+            .map_err(D::Error::custom),
+    }
+}
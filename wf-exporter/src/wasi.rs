@@ -0,0 +1,142 @@
+//! Runs atomic functions given as WASI modules in-process with wasmtime. A module is a WASI
+//! command: its `_start` reads the data inputs as a JSON object from stdin and prints the
+//! data outputs as a JSON object to stdout, like the commands of the
+//! [`runtime`](crate::runtime).
+//!
+//! Modules are sandboxed: they see no files, network or host environment, only the variables
+//! configured for them. Clocks stand still at the epoch and random numbers repeat, so a run
+//! gives the same result every time.
+
+use std::time::Duration;
+
+use wasmtime::{Config, Engine, InstancePre, Linker, Module, Store};
+use wasmtime_wasi::{
+    pipe::{MemoryInputPipe, MemoryOutputPipe},
+    preview1::{self, WasiP1Ctx},
+    Deterministic, HostMonotonicClock, HostWallClock, I32Exit, WasiCtxBuilder,
+};
+
+use crate::{
+    interpreter::{Data, FunctionImpl},
+    runtime::ModuleConfig,
+};
+
+/// Outputs longer than this are cut off and most likely no valid JSON anymore.
+const MAX_OUTPUT: usize = 16 * 1024 * 1024;
+
+pub struct WasiFunction {
+    name: String,
+    engine: Engine,
+    instance: InstancePre<WasiP1Ctx>,
+    env: Vec<(String, String)>,
+    fuel: Option<u64>,
+}
+
+impl WasiFunction {
+    /// Compiles the module once, every call then runs a fresh instance of it. `name` is passed
+    /// to the module as its first argument.
+    pub fn load(name: &str, config: &ModuleConfig) -> Result<Self, String> {
+        let mut engine_config = Config::new();
+        engine_config.consume_fuel(config.fuel.is_some());
+        let engine = Engine::new(&engine_config).map_err(|e| e.to_string())?;
+        let module = Module::from_file(&engine, &config.module)
+            .map_err(|e| format!("{}: {:#}", config.module.display(), e))?;
+        let mut linker = Linker::new(&engine);
+        preview1::add_to_linker_sync(&mut linker, |ctx| ctx).map_err(|e| e.to_string())?;
+        let instance = linker
+            .instantiate_pre(&module)
+            .map_err(|e| format!("{}: {:#}", config.module.display(), e))?;
+
+        let mut env: Vec<(String, String)> = config.env.clone().into_iter().collect();
+        env.sort();
+        Ok(WasiFunction {
+            name: name.to_string(),
+            engine,
+            instance,
+            env,
+            fuel: config.fuel,
+        })
+    }
+}
+
+impl FunctionImpl for WasiFunction {
+    fn call(&self, inputs: &Data) -> Result<Data, String> {
+        let input = serde_json::to_vec(inputs).map_err(|e| e.to_string())?;
+        let stdout = MemoryOutputPipe::new(MAX_OUTPUT);
+        let stderr = MemoryOutputPipe::new(MAX_OUTPUT);
+        let ctx = WasiCtxBuilder::new()
+            .stdin(MemoryInputPipe::new(input))
+            .stdout(stdout.clone())
+            .stderr(stderr.clone())
+            .args(&[&self.name])
+            .envs(&self.env)
+            .secure_random(Deterministic::new(vec![42]))
+            .insecure_random(Deterministic::new(vec![42]))
+            .insecure_random_seed(42)
+            .wall_clock(StoppedClock)
+            .monotonic_clock(StoppedClock)
+            .build_p1();
+
+        let mut store = Store::new(&self.engine, ctx);
+        if let Some(fuel) = self.fuel {
+            store.set_fuel(fuel).map_err(|e| e.to_string())?;
+        }
+        let instance = self
+            .instance
+            .instantiate(&mut store)
+            .map_err(|e| format!("could not instantiate {}: {:#}", self.name, e))?;
+        let start = instance
+            .get_typed_func::<(), ()>(&mut store, "_start")
+            .map_err(|e| format!("{} is no WASI command: {:#}", self.name, e))?;
+        let stderr_text = || {
+            String::from_utf8_lossy(&stderr.contents())
+                .trim()
+                .to_string()
+        };
+        match start.call(&mut store, ()) {
+            Ok(()) => {}
+            Err(e) => match e.downcast_ref::<I32Exit>() {
+                Some(I32Exit(0)) => {}
+                Some(I32Exit(code)) => {
+                    return Err(format!(
+                        "{} exited with {}: {}",
+                        self.name,
+                        code,
+                        stderr_text()
+                    ))
+                }
+                None => return Err(format!("{} trapped: {:#}", self.name, e)),
+            },
+        }
+
+        serde_json::from_slice(&stdout.contents()).map_err(|e| {
+            format!(
+                "{} did not print a JSON object of outputs: {}",
+                self.name, e
+            )
+        })
+    }
+}
+
+/// A clock showing the epoch forever.
+struct StoppedClock;
+
+impl HostWallClock for StoppedClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+
+    fn now(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+impl HostMonotonicClock for StoppedClock {
+    fn resolution(&self) -> u64 {
+        1
+    }
+
+    fn now(&self) -> u64 {
+        0
+    }
+}
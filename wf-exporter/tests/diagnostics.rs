@@ -0,0 +1,869 @@
+//! Checks the diagnostics reported for graphs that cannot be exported as drawn.
+
+use serde_json::{json, Value};
+use wf_exporter::{error::Diagnostics, export_to_yaml, lint::Severity, parse_workflow};
+
+fn node(id: &str, typ: &str, name: &str, parent: Option<&str>) -> Value {
+    let mut node = json!({
+        "id": id,
+        "type": typ,
+        "position": { "x": 0, "y": 0 },
+        "data": { "name": name, "type": typ, "dataIns": [], "dataOuts": [] },
+    });
+    if let Some(parent) = parent {
+        node["parentNode"] = json!(parent);
+    }
+    node
+}
+
+fn edge(source: &str, source_handle: &str, target: &str) -> Value {
+    json!({
+        "source": source,
+        "sourceHandle": source_handle,
+        "target": target,
+        "targetHandle": "idefault",
+    })
+}
+
+fn export(nodes: Vec<Value>, edges: Vec<Value>) -> (Option<String>, Diagnostics) {
+    let graph = json!({ "name": "test", "data": { "nodes": nodes, "edges": edges } });
+    let workflow = parse_workflow(&graph.to_string()).unwrap();
+    let mut diagnostics = Diagnostics::default();
+    let yaml = export_to_yaml(workflow, &mut diagnostics);
+    (yaml, diagnostics)
+}
+
+#[test]
+fn cycle_is_reported_with_node_names() {
+    let (yaml, diagnostics) = export(
+        vec![
+            node("0", "start", "test", None),
+            node("a", "function", "first", None),
+            node("b", "function", "second", None),
+            node("c", "function", "third", None),
+            node("end", "end", "end", None),
+        ],
+        vec![
+            edge("0", "o0", "a"),
+            edge("a", "o0", "b"),
+            edge("b", "o0", "c"),
+            edge("c", "o0", "a"),
+            edge("c", "o0", "end"),
+        ],
+    );
+
+    assert!(yaml.is_none());
+    assert_eq!(diagnostics.errors.len(), 1);
+    let error = &diagnostics.errors[0];
+    assert_eq!(error.kind(), "cycle");
+    assert_eq!(error.node_id(), Some("a"));
+    assert_eq!(
+        error.to_string(),
+        "first -> second -> third -> first form a cycle, use a while node to repeat functions"
+    );
+}
+
+#[test]
+fn cycle_inside_compound_is_reported() {
+    let (yaml, diagnostics) = export(
+        vec![
+            node("0", "start", "test", None),
+            node("loop", "while", "loop", None),
+            node("a", "function", "first", Some("loop")),
+            node("b", "function", "second", Some("loop")),
+            node("end", "end", "end", None),
+        ],
+        vec![
+            edge("0", "o0", "loop"),
+            edge("a", "o0", "b"),
+            edge("b", "o0", "a"),
+            edge("loop", "o0", "end"),
+        ],
+    );
+
+    assert!(yaml.is_none());
+    let kinds: Vec<&str> = diagnostics.errors.iter().map(|e| e.kind()).collect();
+    assert_eq!(kinds, ["cycle"]);
+    assert_eq!(diagnostics.errors[0].node_id(), Some("a"));
+}
+
+#[test]
+fn self_loop_is_a_cycle() {
+    let (_, diagnostics) = export(
+        vec![
+            node("0", "start", "test", None),
+            node("a", "function", "first", None),
+            node("end", "end", "end", None),
+        ],
+        vec![
+            edge("0", "o0", "a"),
+            edge("a", "o0", "a"),
+            edge("a", "o0", "end"),
+        ],
+    );
+
+    assert_eq!(
+        diagnostics.errors[0].to_string(),
+        "first -> first form a cycle, use a while node to repeat functions"
+    );
+}
+
+fn lints(diagnostics: &Diagnostics) -> Vec<(&str, &str, Severity)> {
+    diagnostics
+        .lints
+        .iter()
+        .map(|lint| (lint.rule.id(), lint.node_id.as_str(), lint.severity()))
+        .collect()
+}
+
+#[test]
+fn connected_graph_has_no_lints() {
+    let (yaml, diagnostics) = export(
+        vec![
+            node("0", "start", "test", None),
+            node("a", "function", "first", None),
+            node("end", "end", "end", None),
+        ],
+        vec![edge("0", "o0", "a"), edge("a", "o0", "end")],
+    );
+
+    assert!(yaml.is_some());
+    assert!(diagnostics.lints.is_empty());
+}
+
+#[test]
+fn all_lints_are_reported_at_once() {
+    let mut untyped = node("a", "function", "first", None);
+    untyped["data"]["type"] = json!("");
+    untyped["data"]["dataIns"] = json!([{ "id": "in", "name": "input" }]);
+    let mut end = node("end", "end", "end", None);
+    end["data"]["dataIns"] = json!([{ "id": "out", "name": "result" }]);
+    let mut parallel = node("p", "parallel", "each", None);
+    parallel["data"]["iterators"] = json!([{ "id": "it" }]);
+
+    let (yaml, diagnostics) = export(
+        vec![
+            node("0", "start", "test", None),
+            untyped,
+            node("b", "function", "first", None),
+            parallel,
+            node("c", "function", "inner", Some("p")),
+            node("lost", "function", "lost", None),
+            end,
+        ],
+        vec![
+            edge("0", "o0", "a"),
+            edge("a", "o0", "b"),
+            edge("b", "o0", "p"),
+            edge("p", "o0", "end"),
+        ],
+    );
+
+    assert!(yaml.is_none());
+    assert_eq!(
+        lints(&diagnostics),
+        [
+            ("unreachableNode", "lost", Severity::Warning),
+            ("emptyFunctionType", "a", Severity::Error),
+            ("missingDataSource", "a", Severity::Error),
+            ("duplicateName", "b", Severity::Error),
+            ("unwiredEndInput", "end", Severity::Error),
+            ("parallelWithoutIterator", "p", Severity::Error),
+        ]
+    );
+    assert_eq!(diagnostics.lints[2].data_id.as_deref(), Some("in"));
+}
+
+#[test]
+fn branch_children_need_an_edge_from_the_branch() {
+    let (yaml, diagnostics) = export(
+        vec![
+            node("0", "start", "test", None),
+            node("if", "if", "check", None),
+            node("a", "function", "taken", Some("if")),
+            node("b", "function", "detached", Some("if")),
+            node("end", "end", "end", None),
+        ],
+        vec![
+            edge("0", "o0", "if"),
+            edge("if", "true", "a"),
+            edge("0", "o0", "b"),
+            edge("if", "o0", "end"),
+        ],
+    );
+
+    assert!(yaml.is_some());
+    assert_eq!(
+        lints(&diagnostics),
+        [("unreachableNode", "b", Severity::Warning)]
+    );
+}
+
+fn with_data(mut node: Value, data_ins: Value, data_outs: Value) -> Value {
+    node["data"]["dataIns"] = data_ins;
+    node["data"]["dataOuts"] = data_outs;
+    node
+}
+
+#[test]
+fn number_into_boolean_input_is_a_mismatch() {
+    let (yaml, diagnostics) = export(
+        vec![
+            with_data(
+                node("0", "start", "test", None),
+                json!([]),
+                json!([
+                    { "id": "n", "name": "count", "type": "number" },
+                    { "id": "c", "name": "items", "type": "array" },
+                ]),
+            ),
+            with_data(
+                node("a", "function", "check", None),
+                json!([
+                    { "id": "n", "source": "0", "type": "boolean" },
+                    { "id": "c", "source": "0", "type": "string" },
+                ]),
+                json!([]),
+            ),
+            node("end", "end", "end", None),
+        ],
+        vec![edge("0", "o0", "a"), edge("a", "o0", "end")],
+    );
+
+    assert!(yaml.is_none());
+    let mismatches: Vec<(&str, Option<&str>, String)> = diagnostics
+        .errors
+        .iter()
+        .map(|e| (e.kind(), e.data_id(), e.to_string()))
+        .collect();
+    assert_eq!(
+        mismatches,
+        [
+            (
+                "typeMismatch",
+                Some("n"),
+                "number data is connected where boolean is expected".to_string()
+            ),
+            (
+                "typeMismatch",
+                Some("c"),
+                "array data is connected where string is expected".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn branches_merged_by_an_if_must_agree_on_the_type() {
+    let mut check = with_data(
+        node("if", "if", "check", None),
+        json!([]),
+        json!([{ "id": "t", "source": "a" }, { "id": "f", "source": "b" }]),
+    );
+    check["data"]["ifDataOuts"] =
+        json!([{ "id": "m", "name": "merged", "type": "", "sources": ["t", "f"] }]);
+    let (yaml, diagnostics) = export(
+        vec![
+            node("0", "start", "test", None),
+            check,
+            with_data(
+                node("a", "function", "count", Some("if")),
+                json!([]),
+                json!([{ "id": "t", "name": "out", "type": "number" }]),
+            ),
+            with_data(
+                node("b", "function", "describe", Some("if")),
+                json!([]),
+                json!([{ "id": "f", "name": "out", "type": "string" }]),
+            ),
+            node("end", "end", "end", None),
+        ],
+        vec![
+            edge("0", "o0", "if"),
+            edge("if", "true", "a"),
+            edge("if", "false", "b"),
+            edge("if", "o0", "end"),
+        ],
+    );
+
+    assert!(yaml.is_none());
+    let error = &diagnostics.errors[0];
+    assert_eq!(error.kind(), "typeMismatch");
+    assert_eq!((error.node_id(), error.data_id()), (Some("if"), Some("m")));
+}
+
+#[test]
+fn types_are_inferred_through_compounds_and_renames() {
+    let (yaml, diagnostics) = export(
+        vec![
+            with_data(
+                node("0", "start", "test", None),
+                json!([]),
+                json!([{ "id": "n", "name": "count", "type": "number", "startSource": "count" }]),
+            ),
+            with_data(
+                node("w", "while", "loop", None),
+                json!([{ "id": "n", "source": "0", "rename": "start" }]),
+                json!([{ "id": "s", "source": "a", "rename": "total" }]),
+            ),
+            with_data(
+                node("a", "function", "add", Some("w")),
+                json!([
+                    { "id": "n", "source": "0", "rename": "first" },
+                    { "id": "k", "name": "second", "source": "a", "value": "true" },
+                ]),
+                json!([{ "id": "s", "name": "sum", "type": "number" }]),
+            ),
+            with_data(
+                node("end", "end", "end", None),
+                json!([{ "id": "s", "source": "w", "rename": "result" }]),
+                json!([]),
+            ),
+        ],
+        vec![
+            edge("0", "o0", "w"),
+            edge("w", "i0", "a"),
+            edge("a", "o0", "w"),
+            edge("w", "o0", "end"),
+        ],
+    );
+
+    assert!(diagnostics.errors.is_empty(), "{:?}", diagnostics.errors);
+    let yaml = yaml.unwrap();
+    assert!(!yaml.contains("type: ''"), "{}", yaml);
+    for expected in [
+        "name: start\n      type: number",
+        "name: first\n          type: number",
+        "name: second\n          type: boolean",
+        "name: result\n  type: number",
+    ] {
+        assert!(
+            yaml.contains(expected),
+            "missing {:?} in\n{}",
+            expected,
+            yaml
+        );
+    }
+}
+
+fn while_with_conditions(conditions: Value) -> (Option<String>, Diagnostics) {
+    let constant = |name: &str, typ: &str, value: &str| json!({ "id": name, "name": name, "type": typ, "source": "w", "value": value });
+    let mut repeat = node("w", "while", "repeat", None);
+    repeat["data"]["dataIns"] = json!(["a", "b", "c", "d"]
+        .iter()
+        .map(|name| constant(name, "number", "0"))
+        .chain([
+            constant("done", "boolean", "false"),
+            constant("name", "string", "abc"),
+            constant("items", "array", "[]"),
+        ])
+        .collect::<Vec<_>>());
+    repeat["data"]["conditions"] = conditions;
+    export(
+        vec![
+            node("0", "start", "test", None),
+            repeat,
+            node("a", "function", "step", Some("w")),
+            node("end", "end", "end", None),
+        ],
+        vec![edge("0", "o0", "w"), edge("w", "o0", "end")],
+    )
+}
+
+#[test]
+fn unknown_condition_operators_and_combinators_are_rejected() {
+    let (yaml, diagnostics) = while_with_conditions(json!([
+        { "data1": "repeat/counter", "data2": "3", "type": "number", "operator": "=<" },
+        { "data1": "repeat/counter", "data2": "3", "type": "number", "operator": "<",
+          "combinedWith": "xor" },
+    ]));
+
+    assert!(yaml.is_none());
+    let errors: Vec<(&str, String)> = diagnostics
+        .errors
+        .iter()
+        .map(|e| (e.kind(), e.to_string()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (
+                "unknownConditionOperator",
+                "unknown condition operator =<".to_string()
+            ),
+            (
+                "unknownConditionCombinator",
+                "conditions are combined with and or or, not with xor".to_string()
+            )
+        ]
+    );
+}
+
+#[test]
+fn unknown_group_combinators_are_rejected() {
+    let (yaml, diagnostics) = while_with_conditions(json!({
+        "combinedWith": "nand",
+        "conditions": [
+            condition("repeat/a", "<", "1"),
+            condition("repeat/b", "<", "2"),
+        ],
+    }));
+
+    assert!(yaml.is_none());
+    let errors: Vec<(&str, Option<&str>)> = diagnostics
+        .errors
+        .iter()
+        .map(|e| (e.kind(), e.node_id()))
+        .collect();
+    assert_eq!(errors, vec![("unknownConditionCombinator", Some("w")); 2]);
+    assert_eq!(
+        diagnostics.errors[0].to_string(),
+        "conditions are combined with and or or, not with nand"
+    );
+}
+
+#[test]
+fn operators_must_fit_the_condition_type() {
+    let (_, diagnostics) = while_with_conditions(json!([
+        { "data1": "repeat/counter", "data2": "3", "type": "string", "operator": "<" },
+        { "data1": "repeat/counter", "data2": "3", "type": "number", "operator": "startsWith" },
+        { "data1": "repeat/items", "data2": "a", "type": "array", "operator": "contains" },
+    ]));
+
+    let messages: Vec<String> = diagnostics.errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "operator < cannot compare string data",
+            "operator startsWith cannot compare number data"
+        ]
+    );
+}
+
+#[test]
+fn condition_literals_and_negation_are_typed() {
+    let (yaml, diagnostics) = while_with_conditions(json!([
+        { "data1": "repeat/counter", "data2": "3", "type": "number", "operator": "<",
+          "negation": true, "combinedWith": "and" },
+        { "data1": "repeat/done", "data2": "false", "type": "boolean", "operator": "==" },
+    ]));
+
+    assert!(diagnostics.errors.is_empty(), "{:?}", diagnostics.errors);
+    let yaml = yaml.unwrap();
+    assert!(yaml.contains("data2: 3\n"), "{}", yaml);
+    assert!(yaml.contains("negation: true\n"), "{}", yaml);
+    assert!(yaml.contains("data2: false\n"), "{}", yaml);
+    assert!(yaml.contains("combinedWith: or\n"), "{}", yaml);
+}
+
+fn condition(data1: &str, operator: &str, data2: &str) -> Value {
+    json!({ "data1": data1, "data2": data2, "type": "number", "operator": operator })
+}
+
+fn conditions_of(yaml: &str) -> Vec<(String, String, bool, String)> {
+    let workflow: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+    workflow["workflowBody"][0]["while"]["condition"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .map(|c| {
+            (
+                c["data1"].as_str().unwrap().to_string(),
+                c["operator"].as_str().unwrap().to_string(),
+                c["negation"].as_bool().unwrap(),
+                c["combinedWith"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn nested_groups_combining_the_same_way_are_merged() {
+    let (yaml, diagnostics) = while_with_conditions(json!({
+        "combinedWith": "and",
+        "conditions": [
+            condition("repeat/a", "<", "1"),
+            { "combinedWith": "and", "conditions": [
+                condition("repeat/b", "<", "2"),
+                condition("repeat/c", "<", "3"),
+            ] },
+            { "combinedWith": "or", "conditions": [condition("repeat/d", "<", "4")] },
+        ],
+    }));
+
+    assert!(diagnostics.errors.is_empty(), "{:?}", diagnostics.errors);
+    let conditions = conditions_of(&yaml.unwrap());
+    let expected: Vec<_> = ["repeat/a", "repeat/b", "repeat/c", "repeat/d"]
+        .iter()
+        .map(|d| (d.to_string(), "<".to_string(), false, "and".to_string()))
+        .collect();
+    assert_eq!(conditions, expected);
+}
+
+#[test]
+fn negated_groups_are_rewritten_with_de_morgan() {
+    // !(a < 1 && !(b < 2)) == !(a < 1) || b < 2
+    let (yaml, diagnostics) = while_with_conditions(json!({
+        "combinedWith": "and",
+        "negation": true,
+        "conditions": [
+            condition("repeat/a", "<", "1"),
+            { "combinedWith": "or", "negation": true, "conditions": [
+                { "combinedWith": "and", "conditions": [condition("repeat/b", "<", "2")] },
+            ] },
+        ],
+    }));
+
+    assert!(diagnostics.errors.is_empty(), "{:?}", diagnostics.errors);
+    assert_eq!(
+        conditions_of(&yaml.unwrap()),
+        [
+            (
+                "repeat/a".to_string(),
+                "<".to_string(),
+                true,
+                "or".to_string()
+            ),
+            (
+                "repeat/b".to_string(),
+                "<".to_string(),
+                false,
+                "or".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn mixing_and_with_or_cannot_be_exported() {
+    // (a < 1 && b < 2) || c < 3
+    let (yaml, diagnostics) = while_with_conditions(json!({
+        "combinedWith": "or",
+        "conditions": [
+            { "combinedWith": "and", "conditions": [
+                condition("repeat/a", "<", "1"),
+                condition("repeat/b", "<", "2"),
+            ] },
+            condition("repeat/c", "<", "3"),
+        ],
+    }));
+
+    assert!(yaml.is_none());
+    let kinds: Vec<&str> = diagnostics.errors.iter().map(|e| e.kind()).collect();
+    assert_eq!(kinds, ["inexpressibleCondition"]);
+    assert_eq!(diagnostics.errors[0].node_id(), Some("w"));
+}
+
+#[test]
+fn flat_lists_mixing_and_with_or_cannot_be_exported() {
+    let combined = |data1: &str, combinator: &str| {
+        let mut condition = condition(data1, "<", "1");
+        condition["combinedWith"] = json!(combinator);
+        condition
+    };
+    // each condition stores how it joins the next one, the last one joins nothing
+    let (yaml, diagnostics) = while_with_conditions(json!([
+        combined("repeat/a", "and"),
+        combined("repeat/b", "and"),
+        combined("repeat/c", "or"),
+    ]));
+    assert!(diagnostics.errors.is_empty(), "{:?}", diagnostics.errors);
+    assert!(yaml.is_some());
+
+    // a < 1 || b < 1 && c < 1 as saved by the editor
+    let (yaml, diagnostics) = while_with_conditions(json!([
+        combined("repeat/a", "or"),
+        combined("repeat/b", "and"),
+        combined("repeat/c", "and"),
+    ]));
+    assert!(yaml.is_none());
+    let errors: Vec<(&str, String)> = diagnostics
+        .errors
+        .iter()
+        .map(|e| (e.kind(), e.to_string()))
+        .collect();
+    assert_eq!(
+        errors,
+        [(
+            "inexpressibleCondition",
+            "AFCL combines all conditions with either and or or, conditions mixing both cannot be exported".to_string()
+        )]
+    );
+}
+
+#[test]
+fn misspelled_condition_references_fail_the_export() {
+    let (yaml, diagnostics) = while_with_conditions(json!([
+        { "data1": "repeat/counter", "data2": "repeat/b", "type": "number", "operator": "<" },
+        { "data1": "repaet/a", "data1Kind": "reference", "data2": "1", "type": "number",
+          "operator": "<" },
+        { "data1": "test/missing", "data1Kind": "reference", "data2": "1", "type": "number",
+          "operator": "<" },
+    ]));
+
+    assert!(yaml.is_none());
+    let errors: Vec<(&str, String)> = diagnostics
+        .errors
+        .iter()
+        .map(|e| (e.kind(), e.to_string()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (
+                "unknownConditionData",
+                "condition reads repaet/a, which is no input of the node, an enclosing compound or the workflow".to_string()
+            ),
+            (
+                "unknownConditionData",
+                "condition reads test/missing, which is no input of the node, an enclosing compound or the workflow".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn operands_of_no_kind_naming_no_data_are_literals() {
+    let (yaml, diagnostics) = while_with_conditions(json!([
+        { "data1": "repeat/name", "data2": "s3://bucket/key", "type": "string",
+          "operator": "==" },
+        { "data1": "a/b", "data2": "repeat/name", "type": "string", "operator": "==" },
+    ]));
+
+    assert!(diagnostics.errors.is_empty(), "{:?}", diagnostics.errors);
+    assert!(yaml.unwrap().contains("data2: s3://bucket/key\n"));
+    let warnings: Vec<(&str, String)> = diagnostics
+        .warnings
+        .iter()
+        .map(|w| (w.kind(), w.to_string()))
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            (
+                "literalConditionData",
+                "condition operand s3://bucket/key names no data and is compared as a literal, set its kind to make this explicit".to_string()
+            ),
+            (
+                "literalConditionData",
+                "condition operand a/b names no data and is compared as a literal, set its kind to make this explicit".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn condition_references_resolve_to_inputs_and_loop_outputs() {
+    let mut repeat = with_data(
+        node("w", "while", "repeat", Some("p")),
+        json!([]),
+        json!([]),
+    );
+    repeat["data"]["conditions"] = json!([
+        { "data1": "test/limit", "data2": "step/sum", "type": "number", "operator": "<" },
+        { "data1": "each/items", "data2": "3", "type": "number", "operator": ">" },
+        { "data1": "x", "data1Kind": "literal", "data2": "a/b", "data2Kind": "literal",
+          "type": "string", "operator": "==" },
+    ]);
+    let mut each = with_data(
+        node("p", "parallel", "each", None),
+        json!([{ "id": "i", "source": "0", "rename": "items" }]),
+        json!([]),
+    );
+    each["data"]["iterators"] = json!([{ "id": "it", "name": "items" }]);
+    let (yaml, diagnostics) = export(
+        vec![
+            with_data(
+                node("0", "start", "test", None),
+                json!([]),
+                json!([
+                    { "id": "l", "name": "limit", "type": "number" },
+                    { "id": "i", "name": "items", "type": "array" },
+                ]),
+            ),
+            each,
+            repeat,
+            with_data(
+                node("a", "function", "step", Some("w")),
+                json!([]),
+                json!([{ "id": "s", "name": "sum", "type": "number" }]),
+            ),
+            node("end", "end", "end", None),
+        ],
+        vec![edge("0", "o0", "p"), edge("p", "o0", "end")],
+    );
+
+    assert!(diagnostics.errors.is_empty(), "{:?}", diagnostics.errors);
+    assert!(
+        diagnostics.warnings.is_empty(),
+        "{:?}",
+        diagnostics.warnings
+    );
+    assert!(yaml.unwrap().contains("data2: a/b"));
+}
+
+#[test]
+fn condition_references_must_match_the_condition_type() {
+    let (yaml, diagnostics) = while_with_conditions(json!([
+        { "data1": "repeat/name", "data2": "3", "type": "number", "operator": "<" },
+        { "data1": "repeat/done", "data2": "abc", "type": "string", "operator": "==" },
+    ]));
+
+    assert!(yaml.is_none());
+    let errors: Vec<(&str, String)> = diagnostics
+        .errors
+        .iter()
+        .map(|e| (e.kind(), e.to_string()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (
+                "conditionTypeMismatch",
+                "repeat/name is string data but the condition compares number".to_string()
+            ),
+            (
+                "conditionTypeMismatch",
+                "repeat/done is boolean data but the condition compares string".to_string()
+            ),
+        ]
+    );
+}
+
+fn error_kinds(diagnostics: &Diagnostics) -> Vec<(&str, Option<&str>)> {
+    diagnostics
+        .errors
+        .iter()
+        .map(|e| (e.kind(), e.node_id()))
+        .collect()
+}
+
+#[test]
+fn start_and_end_nodes_are_required() {
+    let (yaml, diagnostics) = export(vec![node("f", "function", "f", None)], vec![]);
+    assert!(yaml.is_none());
+    assert_eq!(error_kinds(&diagnostics), [("missingStartNode", None)]);
+    assert_eq!(diagnostics.errors[0].to_string(), "start node not found");
+
+    let (yaml, diagnostics) = export(vec![node("0", "start", "test", None)], vec![]);
+    assert!(yaml.is_none());
+    assert_eq!(error_kinds(&diagnostics), [("missingEndNode", None)]);
+    assert_eq!(diagnostics.errors[0].to_string(), "end node not found");
+}
+
+#[test]
+fn unknown_node_types_are_rejected() {
+    let (yaml, diagnostics) = export(
+        vec![
+            node("0", "start", "test", None),
+            node("x", "teleport", "beam", None),
+            node("end", "end", "end", None),
+        ],
+        vec![edge("0", "o0", "x"), edge("x", "o0", "end")],
+    );
+    assert!(yaml.is_none());
+    assert_eq!(error_kinds(&diagnostics), [("unknownNodeType", Some("x"))]);
+    assert_eq!(
+        diagnostics.errors[0].to_string(),
+        "unknown or unimplemented node type: teleport"
+    );
+}
+
+#[test]
+fn missing_parents_are_reported() {
+    let (yaml, diagnostics) = export(
+        vec![
+            with_data(
+                node("0", "start", "test", None),
+                json!([]),
+                json!([{ "id": "in", "name": "in", "type": "number" }]),
+            ),
+            with_data(
+                node("f", "function", "orphan", Some("gone")),
+                json!([{ "id": "in", "source": "0" }]),
+                json!([]),
+            ),
+            node("end", "end", "end", None),
+        ],
+        vec![edge("0", "o0", "f"), edge("f", "o0", "end")],
+    );
+    assert!(yaml.is_none());
+    assert_eq!(error_kinds(&diagnostics), [("parentNotFound", Some("f"))]);
+    assert_eq!(
+        diagnostics.errors[0].to_string(),
+        "parent node gone not found"
+    );
+}
+
+#[test]
+fn data_inputs_must_name_an_existing_output() {
+    let (yaml, diagnostics) = export(
+        vec![
+            node("0", "start", "test", None),
+            node("a", "function", "first", None),
+            with_data(
+                node("b", "function", "second", None),
+                json!([
+                    { "id": "ghost-data", "source": "ghost" },
+                    { "id": "no-such-output", "source": "a" },
+                ]),
+                json!([]),
+            ),
+            node("end", "end", "end", None),
+        ],
+        vec![
+            edge("0", "o0", "a"),
+            edge("a", "o0", "b"),
+            edge("b", "o0", "end"),
+        ],
+    );
+    assert!(yaml.is_none());
+    let errors: Vec<(&str, Option<&str>, String)> = diagnostics
+        .errors
+        .iter()
+        .map(|e| (e.kind(), e.data_id(), e.to_string()))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (
+                "sourceNodeNotFound",
+                Some("ghost-data"),
+                "source node ghost of data input not found".to_string()
+            ),
+            (
+                "dataOutNotFound",
+                Some("no-such-output"),
+                "node a has no output matching the data input".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn switch_nodes_need_data_to_evaluate() {
+    let mut switch = with_data(
+        node("s", "switch", "choose", None),
+        json!([{ "id": "m", "name": "mode", "type": "number", "source": "s", "value": "1" }]),
+        json!([]),
+    );
+    switch["data"]["cases"] = json!([{ "id": "c1", "value": "1" }]);
+    let (yaml, diagnostics) = export(
+        vec![
+            node("0", "start", "test", None),
+            switch,
+            node("f", "function", "one", Some("s")),
+            node("end", "end", "end", None),
+        ],
+        vec![
+            edge("0", "o0", "s"),
+            edge("s", "c1", "f"),
+            edge("s", "o0", "end"),
+        ],
+    );
+    assert!(yaml.is_none());
+    assert_eq!(
+        error_kinds(&diagnostics),
+        [("missingSwitchData", Some("s"))]
+    );
+}
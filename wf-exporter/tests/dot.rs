@@ -0,0 +1,157 @@
+//! Graphviz DOT of demos and fixtures, drawn as editor graphs and as exported AFCL.
+
+use std::{fs, path::PathBuf};
+
+use wf_exporter::{
+    dot::{afcl_to_dot, workflow_to_dot},
+    parse_afcl, parse_workflow,
+    spec::load_workflow,
+};
+
+const OUTER_WHILE: &str = "b8758aaa-c8af-4ecb-9199-04427f2a3cce";
+const INNER_WHILE: &str = "fb619d55-724e-44a5-9f03-0e24b56f2a09";
+const ADD: &str = "dca0a06b-8bf8-43c8-80bd-f9eaee1e7109";
+
+fn path(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path)
+}
+
+fn editor_dot(file: &str) -> String {
+    let json = fs::read_to_string(path(file)).unwrap();
+    workflow_to_dot(&parse_workflow(&json).unwrap())
+}
+
+fn lines(dot: &str) -> Vec<&str> {
+    dot.lines().map(str::trim).collect()
+}
+
+/// The lines of the cluster labelled `label`, up to its closing brace.
+fn cluster<'d>(dot: &'d str, label: &str) -> Vec<&'d str> {
+    let lines = lines(dot);
+    let start = lines
+        .iter()
+        .position(|line| *line == format!("label=\"{}\";", label))
+        .unwrap_or_else(|| panic!("no cluster {}", label));
+    let mut depth = 1;
+    lines[start..]
+        .iter()
+        .take_while(|line| {
+            depth += line.matches('{').count();
+            depth -= line.matches('}').count();
+            depth > 0
+        })
+        .copied()
+        .collect()
+}
+
+#[test]
+fn editor_compounds_are_nested_clusters() {
+    let dot = editor_dot("../example-wfs/demos/while_in_while.json");
+    assert!(dot.starts_with("digraph \"while_in_while\" {\n"));
+    assert!(dot.ends_with("}\n"));
+
+    let outer = cluster(&dot, "while (while)");
+    assert!(
+        outer.contains(&format!("\"{}\" [label=\"while\", shape=hexagon];", OUTER_WHILE).as_str())
+    );
+    assert!(outer.contains(&"label=\"innerWhile (while)\";"));
+    let inner = cluster(&dot, "innerWhile (while)");
+    assert!(inner.contains(&format!("\"{}\" [label=\"add\", shape=box];", ADD).as_str()));
+    // the end node is outside of all clusters
+    assert!(!outer.iter().any(|line| line.starts_with("\"end\"")));
+}
+
+#[test]
+fn editor_edges_carry_branches_and_data_names() {
+    let dot = editor_dot("../example-wfs/demos/while_in_while.json");
+    let lines = lines(&dot);
+    assert!(lines.contains(&format!("\"{}\" -> \"{}\";", OUTER_WHILE, INNER_WHILE).as_str()));
+    assert!(lines.contains(
+        &format!(
+            "\"0\" -> \"{}\" [label=\"firstSummand\", style=dashed, color=gray40];",
+            ADD
+        )
+        .as_str()
+    ));
+    // constants are no data flow
+    assert!(!dot.contains("secondSummand"));
+
+    let dot = editor_dot("tests/fixtures/simple_switch.json");
+    assert!(dot.contains("[label=\"case 1\"]"));
+    assert!(dot.contains("[label=\"default\"]"));
+}
+
+#[test]
+fn afcl_bodies_run_in_order_from_inputs_to_outputs() {
+    let workflow = load_workflow(&path("tests/fixtures/simple_switch.json")).unwrap();
+    let dot = afcl_to_dot(&workflow);
+    let switch = cluster(&dot, "switchCompound (switch)");
+    for function in ["switchCompound", "increment", "decrement", "keep"] {
+        assert!(
+            switch
+                .iter()
+                .any(|line| line.contains(&format!("[label=\"{}\"", function))),
+            "{} is not in the switch cluster",
+            function
+        );
+    }
+    let lines = lines(&dot);
+    assert!(lines.contains(&"\"n1\" [label=\"simple_switch\", shape=oval];"));
+    assert!(lines.contains(&"\"n2\" [label=\"end\", shape=oval];"));
+    assert!(lines.contains(&"\"n3\" -> \"n4\" [label=\"case 1\"];"));
+    assert!(lines.contains(&"\"n3\" -> \"n6\" [label=\"default\"];"));
+    assert!(lines.contains(&"\"n3\" -> \"n2\";"));
+    // every branch feeds the merged output
+    for branch in ["n4", "n5", "n6"] {
+        assert!(lines.contains(
+            &format!(
+                "\"{}\" -> \"n3\" [label=\"res\", style=dashed, color=gray40];",
+                branch
+            )
+            .as_str()
+        ));
+    }
+}
+
+#[test]
+fn afcl_sub_fcs_are_clusters_of_their_own() {
+    let workflow = load_workflow(&path("tests/fixtures/sub_fc_calls.json")).unwrap();
+    let dot = afcl_to_dot(&workflow);
+    let sub_fc = cluster(&dot, "addThenSubtract (subFC)");
+    assert!(sub_fc.contains(&"\"n5\" [label=\"addThenSubtract\", shape=component];"));
+    assert!(sub_fc
+        .iter()
+        .any(|line| line.contains("[label=\"subtract\"")));
+    // the body reads the inputs of the sub-FC, not of the calls
+    let lines = lines(&dot);
+    assert!(
+        lines.contains(&"\"n5\" -> \"n6\" [label=\"firstSummand\", style=dashed, color=gray40];")
+    );
+    assert!(lines.contains(&"\"n7\" -> \"n5\" [label=\"result\", style=dashed, color=gray40];"));
+}
+
+#[test]
+fn names_are_quoted() {
+    let workflow = parse_afcl(
+        "name: \"say \\\"hi\\\"\"
+dataIns:
+- name: in
+  type: string
+  source: in
+workflowBody:
+- function:
+    name: \"back\\\\slash\"
+    type: Echo
+    dataIns:
+    - name: text
+      type: string
+      source: \"say \\\"hi\\\"/in\"
+dataOuts: []
+",
+    )
+    .unwrap();
+    let dot = afcl_to_dot(&workflow);
+    assert!(dot.starts_with("digraph \"say \\\"hi\\\"\" {"));
+    assert!(dot.contains("\"n3\" [label=\"back\\\\slash\", shape=box];"));
+    assert!(dot.contains("\"n1\" -> \"n3\" [label=\"text\", style=dashed, color=gray40];"));
+}
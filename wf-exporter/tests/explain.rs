@@ -0,0 +1,157 @@
+//! Explanations of how the exporter resolved the sources of demos and fixtures.
+
+use std::{fs, path::PathBuf};
+
+use serde_json::json;
+use wf_exporter::{
+    error::Diagnostics,
+    explain::{Direction, Explanation, Role, SourceRule},
+    export_to_yaml, parse_workflow,
+};
+
+const INNER_WHILE: &str = "fb619d55-724e-44a5-9f03-0e24b56f2a09";
+const ADD: &str = "dca0a06b-8bf8-43c8-80bd-f9eaee1e7109";
+
+fn explain(path: &str) -> Vec<Explanation> {
+    let json = fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap();
+    let mut diagnostics = Diagnostics::explaining();
+    export_to_yaml(parse_workflow(&json).unwrap(), &mut diagnostics).unwrap();
+    diagnostics.explanations.unwrap()
+}
+
+fn find<'e>(explanations: &'e [Explanation], node: &str, name: &str) -> &'e Explanation {
+    explanations
+        .iter()
+        .find(|e| e.node == node && e.name == name)
+        .unwrap_or_else(|| panic!("no explanation for {} of {}", name, node))
+}
+
+fn visited(explanation: &Explanation) -> Vec<(&str, Role)> {
+    explanation
+        .visited
+        .iter()
+        .map(|visit| (visit.name.as_str(), visit.role))
+        .collect()
+}
+
+#[test]
+fn inputs_of_a_while_body_are_renamed_to_the_enclosing_input() {
+    let explanations = explain("../example-wfs/demos/while_in_while.json");
+
+    let first = find(&explanations, "add", "firstSummand");
+    assert_eq!(first.node_id, ADD);
+    assert_eq!(first.direction, Direction::In);
+    assert_eq!(first.rule, SourceRule::EnclosingInput);
+    assert_eq!(first.source.as_deref(), Some("innerWhile/innerIterator"));
+    assert_eq!(
+        visited(first),
+        [
+            ("while_in_while", Role::Source),
+            ("innerWhile", Role::Enclosing)
+        ]
+    );
+    assert_eq!(first.visited[1].node_id, INNER_WHILE);
+
+    assert_eq!(
+        find(&explanations, "add", "secondSummand").rule,
+        SourceRule::Constant
+    );
+    assert_eq!(
+        find(&explanations, "add", "sum").rule,
+        SourceRule::Unconnected
+    );
+}
+
+#[test]
+fn outputs_climb_the_compounds_around_their_source() {
+    let explanations = explain("../example-wfs/demos/while_in_while.json");
+    let result = explanations
+        .iter()
+        .find(|e| e.name == "overallOutput" && e.direction == Direction::In)
+        .unwrap();
+    assert_eq!(result.rule, SourceRule::Output);
+    assert_eq!(result.source.as_deref(), Some("while/outerIterator"));
+    assert_eq!(
+        visited(result),
+        [
+            ("add", Role::Source),
+            ("innerWhile", Role::SourceParent),
+            ("while", Role::SourceParent)
+        ]
+    );
+}
+
+#[test]
+fn parallel_for_children_read_elements_of_its_inputs() {
+    let explanations = explain("../example-wfs/demos/simple_par_for.json");
+
+    let element = find(&explanations, "addition_Demo", "firstSummand");
+    assert_eq!(element.rule, SourceRule::ParallelForInput);
+    assert_eq!(element.source.as_deref(), Some("parallelFor/parallel_coll"));
+    assert_eq!(
+        visited(element),
+        [
+            ("par_for_simple", Role::Source),
+            ("parallelFor", Role::Parent)
+        ]
+    );
+    // not connected to the parallel for, so an output of a sibling
+    assert_eq!(
+        find(&explanations, "addition_Demo2", "fristSummand").rule,
+        SourceRule::Output
+    );
+}
+
+#[test]
+fn branch_outputs_are_merged_and_read_by_name() {
+    let explanations = explain("tests/fixtures/simple_switch.json");
+
+    let merged = find(&explanations, "switchCompound", "res");
+    assert_eq!(merged.rule, SourceRule::MergedBranches);
+    assert_eq!(merged.direction, Direction::Out);
+    assert_eq!(
+        merged.source.as_deref(),
+        Some("increment/sum,decrement/difference,keep/sum")
+    );
+
+    let result = find(&explanations, "end", "wf_result");
+    assert_eq!(result.rule, SourceRule::BranchOutput);
+    assert_eq!(result.source.as_deref(), Some("switchCompound/res"));
+}
+
+#[test]
+fn data_loops_are_read_from_the_for_node() {
+    let explanations = explain("tests/fixtures/simple_for.json");
+    let acc = find(&explanations, "add", "firstSummand");
+    assert_eq!(acc.rule, SourceRule::DataLoop);
+    assert_eq!(acc.source.as_deref(), Some("forCompound/acc"));
+    assert_eq!(
+        acc.to_string(),
+        "add input firstSummand <- forCompound/acc by dataLoop, visited source forCompound"
+    );
+}
+
+#[test]
+fn explanations_are_only_collected_on_request() {
+    let json = fs::read_to_string(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/simple_for.json"),
+    )
+    .unwrap();
+    let mut diagnostics = Diagnostics::default();
+    export_to_yaml(parse_workflow(&json).unwrap(), &mut diagnostics).unwrap();
+    assert_eq!(diagnostics.explanations, None);
+    assert!(serde_json::to_value(&diagnostics)
+        .unwrap()
+        .get("explanations")
+        .is_none());
+
+    let explanations = explain("tests/fixtures/simple_for.json");
+    let acc = serde_json::to_value(find(&explanations, "add", "firstSummand")).unwrap();
+    assert_eq!(acc["rule"], json!("dataLoop"));
+    assert_eq!(acc["direction"], json!("in"));
+    assert_eq!(acc["visited"][0]["role"], json!("source"));
+    assert_eq!(
+        acc["message"],
+        json!("add input firstSummand <- forCompound/acc by dataLoop, visited source forCompound")
+    );
+}
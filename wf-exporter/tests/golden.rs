@@ -0,0 +1,146 @@
+//! Exports every graph in `example-wfs` and `tests/fixtures` and compares the result
+//! to the expected AFCL in `tests/golden`. Run with `BLESS=1 cargo test --test golden` to update
+//! the expected files after an intended change.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use wf_exporter::{error::Diagnostics, export_to_yaml, parse_workflow};
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn example_graphs() -> Vec<(String, PathBuf)> {
+    let mut graphs = vec![];
+    // fixtures cover constructs the GUI cannot draw yet
+    let groups = [
+        ("demos", manifest_dir().join("../example-wfs/demos")),
+        ("complex", manifest_dir().join("../example-wfs/complex")),
+        ("fixtures", manifest_dir().join("tests/fixtures")),
+    ];
+    for (group, dir) in groups {
+        let mut entries: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap_or_else(|e| panic!("could not read {}: {}", dir.display(), e))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        entries.sort();
+        for path in entries {
+            let name = path.file_stem().unwrap().to_string_lossy();
+            graphs.push((format!("{}/{}", group, name), path));
+        }
+    }
+    graphs
+}
+
+fn export(path: &Path) -> Result<String, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    export_content(&content)
+}
+
+fn export_content(content: &str) -> Result<String, String> {
+    let mut diagnostics = Diagnostics::default();
+    let yaml = parse_workflow(content)
+        .map_err(|e| e.to_string())
+        .map(|workflow| export_to_yaml(workflow, &mut diagnostics))?;
+    yaml.ok_or_else(|| {
+        diagnostics
+            .errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    })
+}
+
+fn first_difference(expected: &str, actual: &str) -> String {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let line = expected_lines
+        .iter()
+        .zip(&actual_lines)
+        .position(|(e, a)| e != a)
+        .unwrap_or(expected_lines.len().min(actual_lines.len()));
+    format!(
+        "first difference in line {}:\n  expected: {}\n  actual:   {}",
+        line + 1,
+        expected_lines.get(line).unwrap_or(&"<end of file>"),
+        actual_lines.get(line).unwrap_or(&"<end of file>")
+    )
+}
+
+#[test]
+fn examples_match_golden_files() {
+    let bless = env::var_os("BLESS").is_some();
+    let graphs = example_graphs();
+    assert!(!graphs.is_empty(), "no example graphs found");
+
+    let mut failures = vec![];
+    for (name, path) in &graphs {
+        let golden = manifest_dir()
+            .join("tests/golden")
+            .join(format!("{}.yaml", name));
+        let actual = match export(path) {
+            Ok(yaml) => yaml,
+            Err(e) => {
+                failures.push(format!("{}: export failed: {}", name, e));
+                continue;
+            }
+        };
+
+        if bless {
+            fs::create_dir_all(golden.parent().unwrap()).unwrap();
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&golden) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{}: output changed, {}",
+                name,
+                first_difference(&expected, &actual)
+            )),
+            Err(_) => failures.push(format!("{}: missing {}", name, golden.display())),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} examples differ from their golden files (rerun with BLESS=1 to update):\n{}",
+        failures.len(),
+        graphs.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn export_does_not_depend_on_saved_order() {
+    for (name, path) in example_graphs() {
+        let content = fs::read_to_string(&path).unwrap();
+        let mut graph: serde_json::Value =
+            serde_json::from_str(content.trim_start_matches('\u{feff}')).unwrap();
+        for list in ["nodes", "edges"] {
+            graph["data"][list].as_array_mut().unwrap().reverse();
+        }
+        assert_eq!(
+            export(&path),
+            export_content(&graph.to_string()),
+            "{}: reversing nodes and edges changed the export",
+            name
+        );
+    }
+}
+
+#[test]
+fn examples_export_without_warnings() {
+    for (name, path) in example_graphs() {
+        let content = fs::read_to_string(&path).unwrap();
+        let mut diagnostics = Diagnostics::default();
+        export_to_yaml(parse_workflow(&content).unwrap(), &mut diagnostics);
+        let warnings: Vec<String> = diagnostics.warnings.iter().map(|w| w.to_string()).collect();
+        assert!(warnings.is_empty(), "{}: {}", name, warnings.join(", "));
+    }
+}
@@ -0,0 +1,85 @@
+//! Imports the expected AFCL in `tests/golden` and hand-written workflows into editor graphs
+//! and exports them again.
+
+use std::{fs, path::PathBuf};
+
+use serde_yaml::Value;
+use wf_exporter::{error::Diagnostics, export_to_yaml, importer::import_to_flow, parse_afcl};
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn golden_files() -> Vec<PathBuf> {
+    let mut files = vec![];
+    for group in ["demos", "complex", "fixtures"] {
+        let dir = manifest_dir().join("tests/golden").join(group);
+        let mut entries: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap_or_else(|e| panic!("could not read {}: {}", dir.display(), e))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "yaml"))
+            .collect();
+        entries.sort();
+        files.extend(entries);
+    }
+    files
+}
+
+/// Imports `yaml` and exports the graph again, failing on any export error.
+fn round_trip(yaml: &str) -> String {
+    let workflow = import_to_flow(parse_afcl(yaml).unwrap());
+    let mut diagnostics = Diagnostics::default();
+    export_to_yaml(workflow, &mut diagnostics).unwrap_or_else(|| {
+        panic!(
+            "could not export the imported graph: {}",
+            diagnostics
+                .errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    })
+}
+
+#[test]
+fn golden_files_survive_a_round_trip() {
+    let files = golden_files();
+    assert!(!files.is_empty(), "no golden files found");
+
+    let mut failures = vec![];
+    for path in files {
+        let expected = fs::read_to_string(&path).unwrap();
+        let actual = round_trip(&expected);
+        let expected: Value = serde_yaml::from_str(&expected).unwrap();
+        let actual: Value = serde_yaml::from_str(&actual).unwrap();
+        if expected != actual {
+            failures.push(format!(
+                "{}:\n{}",
+                path.display(),
+                serde_yaml::to_string(&actual).unwrap()
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn hand_written_workflows_use_afcl_defaults() {
+    let yaml = fs::read_to_string(manifest_dir().join("tests/afcl/hand_written.yaml")).unwrap();
+    let exported: Value = serde_yaml::from_str(&round_trip(&yaml)).unwrap();
+
+    let check = &exported["workflowBody"][0]["if"];
+    let condition = &check["condition"][0];
+    assert_eq!(condition["negation"], Value::from(false));
+    assert_eq!(condition["combinedWith"], Value::from("and"));
+    assert_eq!(condition["data2"], Value::from(10));
+    // numbers are read where AFCL expects strings
+    let loop_counter = &exported["workflowBody"][1]["for"]["loopCounter"];
+    assert_eq!(loop_counter["to"], Value::from("3"));
+    // the branch still reads the input of the if, not the data it is connected to
+    assert_eq!(
+        check["then"][0]["function"]["dataIns"][0]["source"],
+        Value::from("check/n")
+    );
+}
@@ -0,0 +1,213 @@
+//! Runs the golden AFCL of the demos and fixtures with the built-in mock functions.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+use wf_exporter::{
+    error::RunError,
+    exporter::ApolloYaml,
+    interpreter::{Data, Interpreter},
+    parse_afcl,
+};
+
+fn golden(name: &str) -> ApolloYaml {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.yaml", name));
+    parse_afcl(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn data(value: Value) -> Data {
+    value.as_object().unwrap().clone()
+}
+
+fn run(name: &str, input: Value) -> Result<Value, RunError> {
+    Interpreter::new()
+        .run(&golden(name), &data(input))
+        .map(Value::Object)
+}
+
+#[test]
+fn nested_while_loops_feed_outputs_back() {
+    let output = run(
+        "demos/while_in_while",
+        json!({"inputSource": 1, "outerIterator": 2, "innerIterator": 3}),
+    );
+    assert_eq!(output, Ok(json!({"overallOutput": 7})));
+}
+
+#[test]
+fn while_counter_indexes_a_collection() {
+    let output = run("demos/while_coll", json!({"inputSource": [4, 5, 6]}));
+    assert_eq!(output, Ok(json!({"overallOutput": 15})));
+}
+
+#[test]
+fn if_without_else_passes_the_input_through() {
+    // c1 == true && !(c2 startsWith abc), the or of the last condition joins nothing
+    let input = |c1: bool, c2: &str| json!({"a": 2, "b": 3, "wait": 0, "c1": c1, "c2": c2});
+    assert_eq!(
+        run("demos/simple_if_no_else", input(true, "xyz")),
+        Ok(json!({"wf_result": 7}))
+    );
+    assert_eq!(
+        run("demos/simple_if_no_else", input(true, "abc")),
+        Ok(json!({"wf_result": 2}))
+    );
+    assert_eq!(
+        run("demos/simple_if_no_else", input(false, "xyz")),
+        Ok(json!({"wf_result": 2}))
+    );
+}
+
+#[test]
+fn operands_naming_no_data_are_literals() {
+    let path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/demos/simple_if_no_else.yaml");
+    let yaml = fs::read_to_string(path)
+        .unwrap()
+        .replace("data2: abc", "data2: s3://bucket/abc");
+    let workflow = parse_afcl(&yaml).unwrap();
+    let input = json!({"a": 2, "b": 3, "wait": 0, "c1": false, "c2": "s3://bucket/abc/x"});
+    let output = Interpreter::new().run(&workflow, &data(input));
+    assert_eq!(output, Ok(data(json!({"wf_result": 2}))));
+}
+
+#[test]
+fn parallel_for_expands_constrained_iterators() {
+    let output = run(
+        "demos/par_for_complex",
+        json!({"inputSource": [1, 2, 3, 4], "blockSize": 2, "wait": 0}),
+    )
+    .unwrap();
+    assert_eq!(output["output"], json!([2, 2, 2, 3, 3, 3, 4, 4, 4, 5, 5]));
+    assert_eq!(output["output2"], json!(vec![json!([2, 3, 4]); 11]));
+    assert_eq!(output["output3"], json!(vec![json!([[2], [2], [2]]); 11]));
+}
+
+#[test]
+fn for_loop_carries_data_loops() {
+    let output = run("fixtures/simple_for", json!({"s": 2, "n": 3}));
+    assert_eq!(output, Ok(json!({"wf_result": 8})));
+}
+
+#[test]
+fn switch_runs_the_matching_case() {
+    for (mode, expected) in [(1, 11), (2, 9), (3, 10)] {
+        let output = run("fixtures/simple_switch", json!({"a": 10, "m": mode}));
+        assert_eq!(
+            output,
+            Ok(json!({ "wf_result": expected })),
+            "mode {}",
+            mode
+        );
+    }
+}
+
+#[test]
+fn sub_fcs_run_with_their_own_data() {
+    let output = run("fixtures/sub_fc_calls", json!({"a": 1}));
+    assert_eq!(output, Ok(json!({"wf_result": 15})));
+}
+
+#[test]
+fn registered_functions_replace_built_ins() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    let mut interpreter = Interpreter::new();
+    interpreter.register("Addition", move |inputs: &Data| {
+        counted.fetch_add(1, Ordering::SeqCst);
+        let first = inputs["firstSummand"].as_i64().unwrap();
+        Ok(data(json!({ "sum": first * 10 })))
+    });
+    let output = interpreter.run(
+        &golden("demos/simple_if_no_else"),
+        &data(json!({"a": 2, "b": 3, "wait": 0, "c1": true, "c2": ""})),
+    );
+    assert_eq!(output, Ok(data(json!({"wf_result": 20}))));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn failures_name_the_function() {
+    assert_eq!(
+        run("demos/simple_if_no_else", json!({"a": 2})),
+        Err(RunError::MissingInput {
+            name: "b".to_string()
+        })
+    );
+
+    let mut interpreter = Interpreter::new();
+    interpreter.register("Addition", |_: &Data| Err("out of coffee".to_string()));
+    let error = interpreter
+        .run(
+            &golden("fixtures/simple_for"),
+            &data(json!({"s": 2, "n": 3})),
+        )
+        .unwrap_err();
+    assert_eq!(error.function(), Some("add"));
+    assert_eq!(error.to_string(), "add failed: out of coffee");
+}
+
+#[test]
+fn endless_loops_stop() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_iterations(5);
+    let error = interpreter
+        .run(
+            &golden("demos/while_in_while"),
+            &data(json!({"inputSource": 1, "outerIterator": 2, "innerIterator": 30})),
+        )
+        .unwrap_err();
+    assert_eq!(
+        error,
+        RunError::IterationLimit {
+            function: "innerWhile".to_string(),
+            limit: 5
+        }
+    );
+}
+
+#[test]
+fn parallel_for_iterations_run_on_workers() {
+    let active = Arc::new(AtomicUsize::new(0));
+    let most_active = Arc::new(AtomicUsize::new(0));
+    let (counter, most) = (active.clone(), most_active.clone());
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .set_workers(3)
+        .register("Addition", move |inputs: &Data| {
+            let now = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            most.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            counter.fetch_sub(1, Ordering::SeqCst);
+            let sum: i64 = inputs
+                .iter()
+                .filter(|(name, _)| *name != "waitTimeIn")
+                .map(|(_, value)| value.as_i64().unwrap())
+                .sum();
+            Ok(data(json!({ "sum": sum })))
+        });
+
+    let output = interpreter.run(
+        &golden("demos/par_for_complex"),
+        &data(json!({"inputSource": [1, 2, 3, 4], "blockSize": 2, "wait": 0})),
+    );
+    let output = Value::Object(output.unwrap());
+    assert_eq!(output["output"], json!([2, 2, 2, 3, 3, 3, 4, 4, 4, 5, 5]));
+    let most_active = most_active.load(Ordering::SeqCst);
+    assert!(
+        (2..=3).contains(&most_active),
+        "{} functions ran at once",
+        most_active
+    );
+}
@@ -0,0 +1,109 @@
+//! Logs of an Apollo run of a demo, joined to the nodes of its editor graph.
+
+use std::{fs, path::PathBuf};
+
+use wf_exporter::{
+    error::LogError,
+    logs::{overlay, parse_log, LogFormat, NodeStats, Overlay},
+    parse_afcl,
+    spec::load_workflow,
+};
+
+const ADDITION: &str = "a4b254dc-23e8-4460-8d7d-bdeb07c75510";
+const ADDITION_2: &str = "ac4119d6-b4ab-4578-b73e-9743e2bd6a98";
+const ADDITION_3: &str = "333cf670-1881-4975-a216-848103ab96bf";
+const SUM: &str = "ee2aa5cb-3edc-4b6e-9186-d1e2de767301";
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn simple_par_for(log: &str) -> Overlay {
+    let workflow =
+        load_workflow(&manifest_dir().join("../example-wfs/demos/simple_par_for.json")).unwrap();
+    let log = fs::read_to_string(manifest_dir().join("tests/logs").join(log)).unwrap();
+    overlay(&workflow, &parse_log(&log).unwrap())
+}
+
+#[test]
+fn calls_are_joined_to_editor_nodes() {
+    let overlay = simple_par_for("simple_par_for.csv");
+    let keys: Vec<&str> = overlay.nodes.keys().map(String::as_str).collect();
+    assert_eq!(keys, [ADDITION_3, ADDITION, ADDITION_2, SUM]);
+    assert_eq!(overlay.unmatched, ["oldFunction"]);
+
+    assert_eq!(
+        overlay.nodes[ADDITION],
+        NodeStats {
+            function: "addition_Demo".to_string(),
+            calls: 3,
+            failures: 1,
+            iterations: 2,
+            total_duration: 1000.0,
+            min_duration: 200.0,
+            max_duration: 400.0,
+            mean_duration: 1000.0 / 3.0,
+            heat: 1.0,
+        }
+    );
+    assert_eq!(overlay.nodes[ADDITION_2].iterations, 2);
+    assert_eq!(overlay.nodes[ADDITION_2].failures, 0);
+    assert_eq!(overlay.nodes[ADDITION_2].heat, 0.4);
+    assert_eq!(overlay.nodes[SUM].iterations, 1);
+    assert_eq!(overlay.nodes[SUM].heat, 0.1);
+}
+
+#[test]
+fn json_and_csv_logs_are_read_alike() {
+    assert_eq!(
+        simple_par_for("simple_par_for.json"),
+        simple_par_for("simple_par_for.csv")
+    );
+
+    let array = r#"[{"function": "add", "start": 0, "end": 1.5, "success": true}]"#;
+    let records = parse_log(array).unwrap();
+    assert_eq!(records[0].duration(), 1.5);
+    assert!(!records[0].failed());
+}
+
+#[test]
+fn format_is_detected_from_the_content() {
+    assert_eq!(LogFormat::detect("\u{feff} [{}]"), LogFormat::Json);
+    assert_eq!(LogFormat::detect("{\"function\": \"a\"}"), LogFormat::Json);
+    assert_eq!(LogFormat::detect("function,start,end"), LogFormat::Csv);
+}
+
+#[test]
+fn invalid_logs_are_rejected() {
+    let error = parse_log("function,start\nadd,0").unwrap_err();
+    assert_eq!(error.kind(), "invalidCsv");
+    assert!(
+        error.to_string().contains("missing field `end`"),
+        "{}",
+        error
+    );
+
+    let error = parse_log(r#"[{"function": "add", "start": "soon", "end": 1}]"#).unwrap_err();
+    assert_eq!(error.kind(), "invalidJson");
+
+    assert_eq!(
+        parse_log("function,start,end\nadd,0,1\nadd,5,2").unwrap_err(),
+        LogError::NegativeDuration {
+            function: "add".to_string(),
+            record: 2,
+        }
+    );
+}
+
+#[test]
+fn functions_of_afcl_are_keyed_by_name() {
+    let yaml =
+        fs::read_to_string(manifest_dir().join("tests/golden/demos/simple_par_for.yaml")).unwrap();
+    let workflow = parse_afcl(&yaml).unwrap();
+    let records = parse_log("function,start,end\naddition_Demo,0,10\nparallelFor,0,30").unwrap();
+    let overlay = overlay(&workflow, &records);
+    assert_eq!(overlay.nodes["addition_Demo"].calls, 1);
+    assert_eq!(overlay.nodes["addition_Demo"].heat, 10.0 / 30.0);
+    assert_eq!(overlay.nodes["parallelFor"].heat, 1.0);
+    assert!(overlay.unmatched.is_empty());
+}
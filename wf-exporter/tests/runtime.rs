@@ -0,0 +1,73 @@
+//! Runs functions as local commands, needs a unix shell.
+#![cfg(unix)]
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use serde_json::{json, Value};
+use wf_exporter::{
+    interpreter::{Data, FunctionImpl, Interpreter},
+    parse_afcl,
+    runtime::{CommandFunction, RuntimeConfig},
+};
+
+fn data(value: Value) -> Data {
+    value.as_object().unwrap().clone()
+}
+
+fn shell(script: &str) -> CommandFunction {
+    CommandFunction {
+        command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+        cwd: None,
+        env: HashMap::new(),
+    }
+}
+
+#[test]
+fn inputs_go_to_stdin_and_outputs_come_from_stdout() {
+    let inputs = data(json!({"firstSummand": 1, "name": "x"}));
+    assert_eq!(shell("cat").call(&inputs), Ok(inputs));
+}
+
+#[test]
+fn environment_is_passed_to_the_command() {
+    let mut function = shell("cat > /dev/null; echo \"{\\\"sum\\\": $SUM}\"");
+    function.env.insert("SUM".to_string(), "42".to_string());
+    assert_eq!(function.call(&Data::new()), Ok(data(json!({"sum": 42}))));
+}
+
+#[test]
+fn failing_commands_report_stderr() {
+    let error = shell("echo broken >&2; exit 3")
+        .call(&Data::new())
+        .unwrap_err();
+    assert!(error.contains("broken"), "{}", error);
+
+    let error = shell("echo done").call(&Data::new()).unwrap_err();
+    assert!(error.contains("did not print a JSON object"), "{}", error);
+}
+
+#[test]
+fn config_runs_commands_in_its_directory() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let config = RuntimeConfig::load(&dir.join("runtime/runtime.json")).unwrap();
+    let mut interpreter = Interpreter::new();
+    config.configure(&mut interpreter).unwrap();
+
+    let workflow =
+        parse_afcl(&fs::read_to_string(dir.join("golden/demos/simple_par_for.yaml")).unwrap())
+            .unwrap();
+    let output = interpreter.run(&workflow, &data(json!({"inputSource": [5, 6, 7]})));
+    // every addition returns 1, so the slice takes the first element only
+    assert_eq!(output, Ok(data(json!({"overallOutput": 1}))));
+}
+
+#[test]
+#[cfg(not(feature = "wasi"))]
+fn modules_need_the_wasi_feature() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/runtime/wasi.json");
+    let error = RuntimeConfig::load(&path)
+        .unwrap()
+        .configure(&mut Interpreter::new())
+        .unwrap_err();
+    assert!(error.contains("rebuild with the wasi feature"), "{}", error);
+}
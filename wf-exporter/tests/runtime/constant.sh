@@ -0,0 +1,3 @@
+# prints the same outputs for every call, whatever the inputs
+cat > /dev/null
+echo '{"sum": 1}'
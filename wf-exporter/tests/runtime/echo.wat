@@ -0,0 +1,20 @@
+;; WASI command copying stdin to stdout, so its outputs are its inputs
+(module
+  (import "wasi_snapshot_preview1" "fd_read"
+    (func $fd_read (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write"
+    (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 2)
+  ;; iovec at 0, byte count at 16, buffer from 1024
+  (func (export "_start")
+    (local $read i32)
+    (block $done
+      (loop $copy
+        (i32.store (i32.const 0) (i32.const 1024))
+        (i32.store (i32.const 4) (i32.const 64512))
+        (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 16)))
+        (local.set $read (i32.load (i32.const 16)))
+        (br_if $done (i32.eqz (local.get $read)))
+        (i32.store (i32.const 4) (local.get $read))
+        (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 16)))
+        (br $copy)))))
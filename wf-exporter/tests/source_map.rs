@@ -0,0 +1,151 @@
+//! Source maps of exported demos and fixtures, linking yaml lines to editor nodes.
+
+use std::{fs, path::PathBuf};
+
+use wf_exporter::{
+    error::Diagnostics, export_to_yaml_with_source_map, parse_afcl, parse_workflow,
+    source_map::SourceMap,
+};
+
+const OUTER_WHILE: &str = "b8758aaa-c8af-4ecb-9199-04427f2a3cce";
+const INNER_WHILE: &str = "fb619d55-724e-44a5-9f03-0e24b56f2a09";
+const ADD: &str = "dca0a06b-8bf8-43c8-80bd-f9eaee1e7109";
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn export(path: &str) -> (String, SourceMap) {
+    let json = fs::read_to_string(manifest_dir().join(path)).unwrap();
+    let workflow = parse_workflow(&json).unwrap();
+    export_to_yaml_with_source_map(workflow, &mut Diagnostics::default()).unwrap()
+}
+
+fn line(yaml: &str, line: usize) -> &str {
+    yaml.lines().nth(line - 1).unwrap().trim()
+}
+
+#[test]
+fn functions_and_data_map_to_their_lines() {
+    let (yaml, map) = export("../example-wfs/demos/while_in_while.json");
+
+    let outer = map.at_path("workflowBody[0]").unwrap();
+    assert_eq!(outer.node_id, OUTER_WHILE);
+    assert_eq!(outer.function, "while");
+    assert_eq!(line(&yaml, outer.line), "- while:");
+    // ends on the line before the workflow outputs
+    assert_eq!(
+        outer.end_line,
+        yaml.lines().position(|l| l == "dataOuts:").unwrap()
+    );
+
+    let summand = map.find("add", Some("firstSummand")).unwrap();
+    assert_eq!(
+        summand.path,
+        "workflowBody[0].while.loopBody[0].while.loopBody[0].function.dataIns[0]"
+    );
+    assert_eq!(summand.node_id, ADD);
+    assert_eq!(line(&yaml, summand.line), "- name: firstSummand");
+    assert_eq!(summand.end_line, summand.line + 2);
+
+    let result = map.find("while_in_while", Some("overallOutput")).unwrap();
+    assert_eq!(result.node_id, "end");
+    assert_eq!(result.path, "dataOuts[0]");
+}
+
+#[test]
+fn lines_map_to_the_innermost_element() {
+    let (yaml, map) = export("../example-wfs/demos/while_in_while.json");
+    assert_eq!(map.at_line(1), None);
+
+    let summand = map.find("add", Some("secondSummand")).unwrap();
+    assert_eq!(map.at_line(summand.line + 1), Some(summand));
+
+    let loop_body = yaml
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| l.trim() == "loopBody:")
+        .map(|(i, _)| i + 1)
+        .last()
+        .unwrap();
+    let inner = map.at_line(loop_body).unwrap();
+    assert_eq!(inner.node_id, INNER_WHILE);
+    assert_eq!(inner.data, None);
+}
+
+#[test]
+fn nodes_map_back_to_the_yaml() {
+    let (yaml, map) = export("../example-wfs/demos/while_in_while.json");
+    let add = map.of_node(ADD, None).unwrap();
+    assert_eq!(line(&yaml, add.line), "- function:");
+    assert_eq!(line(&yaml, add.line + 1), "name: add");
+
+    let sum = map
+        .of_node(ADD, Some("d67013c8-7dc9-4635-8ab8-ccf790cc67df"))
+        .unwrap();
+    assert_eq!(sum.data.as_deref(), Some("sum"));
+    assert_eq!(map.of_node("unknown", None), None);
+}
+
+#[test]
+fn sub_fcs_and_switches_are_mapped() {
+    let (yaml, map) = export("tests/fixtures/sub_fc_calls.json");
+    let definition = map.at_path("subFCs[0]").unwrap();
+    assert_eq!(definition.function, "addThenSubtract");
+    assert_eq!(line(&yaml, definition.line), "- name: addThenSubtract");
+    let difference = map.find("subtract", Some("difference")).unwrap();
+    assert_eq!(
+        difference.path,
+        "subFCs[0].subFCBody[1].function.dataOuts[0]"
+    );
+    assert_eq!(
+        map.find("secondCall", None).unwrap().path,
+        "workflowBody[1]"
+    );
+
+    let (yaml, map) = export("tests/fixtures/simple_switch.json");
+    let eval = map.at_path("workflowBody[0].switch.dataEval").unwrap();
+    assert_eq!(eval.data.as_deref(), Some("m"));
+    assert_eq!(line(&yaml, eval.line), "name: m");
+    let keep = map.find("keep", None).unwrap();
+    assert_eq!(keep.path, "workflowBody[0].switch.default[0]");
+}
+
+#[test]
+fn every_entry_points_at_its_name() {
+    let demos = manifest_dir().join("../example-wfs/demos");
+    let mut paths: Vec<PathBuf> = fs::read_dir(demos)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    for path in paths {
+        let workflow = parse_workflow(&fs::read_to_string(&path).unwrap()).unwrap();
+        let (yaml, map) =
+            export_to_yaml_with_source_map(workflow, &mut Diagnostics::default()).unwrap();
+        assert!(!map.entries.is_empty(), "{}", path.display());
+        for entry in &map.entries {
+            let name = match &entry.data {
+                Some(data) => format!("name: {}", data),
+                None => format!("name: {}", entry.function),
+            };
+            let text = &yaml.lines().collect::<Vec<_>>()[entry.line - 1..=entry.line];
+            assert!(
+                text.iter()
+                    .any(|l| l.trim().trim_start_matches("- ") == name),
+                "{}: {} is at {:?}",
+                path.display(),
+                entry.path,
+                text
+            );
+        }
+    }
+}
+
+#[test]
+fn afcl_has_no_editor_nodes_to_map_to() {
+    let yaml =
+        fs::read_to_string(manifest_dir().join("tests/golden/demos/while_in_while.yaml")).unwrap();
+    let workflow = parse_afcl(&yaml).unwrap();
+    assert!(SourceMap::build(&workflow, &yaml).entries.is_empty());
+}
@@ -0,0 +1,40 @@
+//! Runs the workflow test specs in `tests/specs`, the same as `wf-test tests/specs`.
+
+use std::{fs, path::PathBuf};
+
+use wf_exporter::spec::run_spec_file;
+
+#[test]
+fn workflow_specs_pass() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/specs");
+    let mut specs: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    specs.sort();
+    assert!(!specs.is_empty(), "no specs found in {}", dir.display());
+
+    let mut failures = vec![];
+    for spec in &specs {
+        let name = spec.file_stem().unwrap().to_string_lossy();
+        match run_spec_file(spec) {
+            Ok(results) => failures.extend(results.iter().filter(|result| !result.passed()).map(
+                |result| {
+                    format!(
+                        "{}: {}: {}",
+                        name,
+                        result.name,
+                        result.failure.as_deref().unwrap_or_default()
+                    )
+                },
+            )),
+            Err(e) => failures.push(format!("{}: {}", name, e)),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "failing specs:\n{}",
+        failures.join("\n")
+    );
+}
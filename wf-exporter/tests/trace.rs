@@ -0,0 +1,197 @@
+//! Traces of demo workflows exported from the editor, so calls carry editor node ids.
+
+use std::{fs, path::PathBuf};
+
+use serde_json::{json, Value};
+use wf_exporter::{
+    exporter::ApolloYaml,
+    interpreter::{Data, Interpreter},
+    spec::load_workflow,
+    trace::{LoopKind, NodeStatus, Replay, Trace},
+};
+
+const ADD: &str = "dca0a06b-8bf8-43c8-80bd-f9eaee1e7109";
+const OUTER_WHILE: &str = "b8758aaa-c8af-4ecb-9199-04427f2a3cce";
+const INNER_WHILE: &str = "fb619d55-724e-44a5-9f03-0e24b56f2a09";
+
+fn demo(name: &str) -> ApolloYaml {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../example-wfs/demos")
+        .join(format!("{}.json", name));
+    load_workflow(&path).unwrap()
+}
+
+fn data(value: Value) -> Data {
+    value.as_object().unwrap().clone()
+}
+
+fn while_in_while(interpreter: &Interpreter) -> Trace {
+    let input = json!({"inputSource": 1, "outerIterator": 2, "innerIterator": 3});
+    let (result, trace) = interpreter.run_traced(&demo("while_in_while"), &data(input));
+    assert_eq!(result.map(Value::Object), Ok(json!({"overallOutput": 7})));
+    trace
+}
+
+#[test]
+fn calls_are_keyed_by_function_and_editor_node() {
+    let trace = while_in_while(&Interpreter::new());
+    assert_eq!(trace.workflow, "while_in_while");
+    assert_eq!(
+        trace.outputs.map(Value::Object),
+        Some(json!({"overallOutput": 7}))
+    );
+    assert_eq!(trace.error, None);
+    assert_eq!(trace.events.len(), 6);
+    for (seq, event) in trace.events.iter().enumerate() {
+        assert_eq!(event.seq, seq);
+        assert_eq!(event.function, "add");
+        assert_eq!(event.key(), ADD);
+        assert_eq!(event.typ, "Addition");
+        assert!(event.started_at <= event.ended_at);
+    }
+    assert_eq!(trace.events[0].inputs.get("firstSummand"), Some(&json!(1)));
+    assert_eq!(
+        trace.events[0].outputs.as_ref().and_then(|o| o.get("sum")),
+        Some(&json!(2))
+    );
+}
+
+#[test]
+fn calls_know_the_loops_around_them() {
+    let trace = while_in_while(&Interpreter::new());
+    let indices: Vec<(usize, usize)> = trace
+        .events
+        .iter()
+        .map(|event| {
+            let loops: Vec<_> = event
+                .loops
+                .iter()
+                .map(|frame| (frame.node_id.as_deref().unwrap(), frame.kind))
+                .collect();
+            assert_eq!(
+                loops,
+                [
+                    (OUTER_WHILE, LoopKind::While),
+                    (INNER_WHILE, LoopKind::While)
+                ]
+            );
+            (event.loops[0].index, event.iteration().unwrap())
+        })
+        .collect();
+    assert_eq!(indices, [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]);
+    assert_eq!(trace.events[0].element(), None);
+}
+
+#[test]
+fn parallel_for_calls_know_their_element() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_workers(3);
+    let input = data(json!({"inputSource": [1, 2, 3, 4, 5, 6]}));
+    let (result, trace) = interpreter.run_traced(&demo("simple_par_for"), &input);
+    assert!(result.is_ok());
+
+    let mut elements: Vec<(String, usize)> = trace
+        .events
+        .iter()
+        .filter_map(|event| Some((event.function.clone(), event.element()?)))
+        .collect();
+    elements.sort();
+    let iterations = elements.len() / 2;
+    assert!(iterations > 1);
+    let expected: Vec<(String, usize)> = ["addition_Demo", "addition_Demo2"]
+        .iter()
+        .flat_map(|name| (0..iterations).map(move |i| (name.to_string(), i)))
+        .collect();
+    assert_eq!(elements, expected);
+    // calls of all workers share one sequence without gaps
+    let seqs: Vec<usize> = trace.events.iter().map(|event| event.seq).collect();
+    assert_eq!(seqs, (0..trace.events.len()).collect::<Vec<_>>());
+}
+
+#[test]
+fn replay_steps_through_starts_and_ends() {
+    let trace = while_in_while(&Interpreter::new());
+    let mut replay = Replay::new(&trace);
+    assert_eq!(replay.steps(), 12);
+    assert!(replay.state().is_empty());
+    assert!(replay.current().is_none());
+
+    assert!(replay.forward());
+    let state = replay.state();
+    assert_eq!(state[ADD].status, NodeStatus::Running);
+    assert_eq!(state[ADD].calls, 0);
+    assert_eq!(state[ADD].outputs, None);
+
+    assert!(replay.forward());
+    assert_eq!(replay.state()[ADD].status, NodeStatus::Done);
+    assert_eq!(replay.state()[ADD].calls, 1);
+    assert_eq!(replay.current().map(|event| event.seq), Some(0));
+
+    replay.seek(usize::MAX);
+    assert_eq!(replay.position(), 12);
+    assert!(!replay.forward());
+    let state = replay.state();
+    assert_eq!(state[ADD].calls, 6);
+    assert_eq!(state[ADD].loops[0].index, 1);
+    assert_eq!(
+        state[ADD].outputs.as_ref().and_then(|o| o.get("sum")),
+        Some(&json!(7))
+    );
+
+    assert!(replay.back());
+    assert_eq!(replay.state()[ADD].status, NodeStatus::Running);
+    replay.seek(0);
+    assert!(!replay.back());
+}
+
+#[test]
+fn failed_runs_are_traced_up_to_the_failure() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register("Addition", |_: &Data| -> Result<Data, String> {
+        Err("out of coffee".to_string())
+    });
+    let input = json!({"inputSource": 1, "outerIterator": 2, "innerIterator": 3});
+    let (result, trace) = interpreter.run_traced(&demo("while_in_while"), &data(input));
+    assert!(result.is_err());
+    assert_eq!(trace.error, Some("add failed: out of coffee".to_string()));
+    assert_eq!(trace.outputs, None);
+    assert_eq!(trace.events.len(), 1);
+
+    let mut replay = Replay::new(&trace);
+    replay.seek(2);
+    let state = replay.state();
+    assert_eq!(state[ADD].status, NodeStatus::Failed);
+    assert_eq!(
+        state[ADD].error.as_deref(),
+        Some("add failed: out of coffee")
+    );
+}
+
+#[test]
+fn traces_read_back_from_json() {
+    let trace = while_in_while(&Interpreter::new());
+    let path = std::env::temp_dir().join(format!("wf-trace-{}.json", std::process::id()));
+    fs::write(&path, serde_json::to_string(&trace).unwrap()).unwrap();
+    let read: Trace = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(read, trace);
+
+    let event = serde_json::to_value(&trace.events[0]).unwrap();
+    assert_eq!(event["nodeId"], json!(ADD));
+    assert_eq!(event["type"], json!("Addition"));
+    assert_eq!(event["loops"][1]["kind"], json!("while"));
+}
+
+#[test]
+fn workflows_read_from_afcl_are_keyed_by_function_name() {
+    let yaml = fs::read_to_string(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden/demos/while_in_while.yaml"),
+    )
+    .unwrap();
+    let workflow = wf_exporter::parse_afcl(&yaml).unwrap();
+    let input = json!({"inputSource": 1, "outerIterator": 0, "innerIterator": 0});
+    let (_, trace) = Interpreter::new().run_traced(&workflow, &data(input));
+    assert_eq!(trace.events[0].node_id, None);
+    assert_eq!(trace.events[0].key(), "add");
+    assert_eq!(trace.events[0].loops[0].function, "while");
+}
@@ -0,0 +1,59 @@
+//! Runs functions as WASI modules, only built with `--features wasi`.
+#![cfg(feature = "wasi")]
+
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+use wf_exporter::{
+    interpreter::{Data, FunctionImpl},
+    runtime::{ModuleConfig, RuntimeConfig},
+    wasi::WasiFunction,
+};
+
+fn data(value: Value) -> Data {
+    value.as_object().unwrap().clone()
+}
+
+fn module(name: &str, fuel: Option<u64>) -> WasiFunction {
+    let config = ModuleConfig {
+        module: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/runtime")
+            .join(name),
+        env: Default::default(),
+        fuel,
+    };
+    WasiFunction::load(name, &config).unwrap()
+}
+
+#[test]
+fn inputs_go_to_stdin_and_outputs_come_from_stdout() {
+    let inputs = data(json!({"firstSummand": 1, "name": "x"}));
+    let echo = module("echo.wat", None);
+    assert_eq!(echo.call(&inputs), Ok(inputs.clone()));
+    // every call gets a fresh instance
+    assert_eq!(echo.call(&inputs), Ok(inputs));
+}
+
+#[test]
+fn exit_codes_report_stderr() {
+    assert_eq!(
+        module("fail.wat", None).call(&Data::new()),
+        Err("fail.wat exited with 3: broken".to_string())
+    );
+}
+
+#[test]
+fn fuel_stops_endless_modules() {
+    let error = module("spin.wat", Some(100_000))
+        .call(&Data::new())
+        .unwrap_err();
+    assert!(error.starts_with("spin.wat trapped"), "{}", error);
+}
+
+#[test]
+fn config_resolves_modules_next_to_it() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/runtime/wasi.json");
+    let config = RuntimeConfig::load(&path).unwrap();
+    assert_eq!(config.modules.len(), 3);
+    assert!(config.modules["Echo"].module.is_file());
+}